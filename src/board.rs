@@ -1,5 +1,17 @@
 use std::ops::{Add, Sub, Mul, Index, IndexMut};
 
+mod bitboard;
+mod fen;
+mod magic;
+mod movelist;
+mod notation;
+mod zobrist;
+pub use bitboard::*;
+pub use fen::FenError;
+pub use magic::{rook_attacks, bishop_attacks, queen_attacks};
+pub use movelist::{MoveList, Stage};
+pub use notation::{square_name, parse_square};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Kind {
     Pawn,
@@ -102,12 +114,8 @@ impl Loc {
 impl Index<Loc> for Board {
     type Output = Option<Piece>;
     fn index(&self, ix: Loc) -> &Self::Output {
-        &self.positions[(7-ix.1) as usize][ix.0 as usize]
-    }
-}
-impl IndexMut<Loc> for Board {
-    fn index_mut(&mut self, ix: Loc) -> &mut Self::Output {
-        &mut self.positions[(7-ix.1) as usize][ix.0 as usize]
+        debug_assert!(ix.is_valid());
+        &self.squares[square(ix)]
     }
 }
 impl Add<Loc> for Loc {
@@ -147,13 +155,41 @@ pub struct Move {
     pub castle: Option<CastleMove>
 }
 
+// The parts of a position that can't be worked out from the move when taking it back
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Undo {
+    pub castling: CastleState,
+    pub en_passant_file: Option<isize>,
+    pub moves_since_progress: usize,
+    pub hash: u64,
+}
+
+// Pieces are kept both per square, so Index<Loc> can hand out references, and as bitboards per
+// kind and per color for fast move generation. Board::put keeps the two in sync.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Board {
-    pub positions: [[Option<Piece>; 8]; 8],
+    squares: [Option<Piece>; 64],
+    kinds: [Bitboard; 6],
+    colors: [Bitboard; 2],
     pub castling: CastleState,
     pub moves_since_progress: usize,
     pub en_passant_file: Option<isize>,
     pub turn: Color,
+    // Starts at 1 and increases after each move by Black
+    pub fullmove_number: usize,
+    // Zobrist hash of the position, kept up to date by put, apply_move and consider_move
+    pub hash: u64,
+    // Hashes of the positions before each move played so far, oldest first
+    pub history: Vec<u64>,
+    pub repetition_rule: RepetitionRule,
+}
+
+// When repeating a position ends the game. FIDE lets a player claim a draw on the third
+// occurrence, and ends the game automatically on the fifth.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RepetitionRule {
+    Threefold,
+    Fivefold,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -164,173 +200,147 @@ pub enum WinState {
 }
 
 impl Board {
-    pub fn new() -> Board {
-        use Kind::*;
-        use Color::*;
+    // A board with no pieces, White to move and no castling rights
+    fn empty() -> Board {
         Board {
-            positions: [ 
-                [ Some(Piece { kind: Rook, color: Black }) , Some(Piece { kind: Knight, color: Black }), Some(Piece { kind: Bishop, color: Black }), Some(Piece { kind: Queen, color: Black }), Some(Piece { kind: King, color: Black }), Some(Piece { kind: Bishop, color: Black }), Some(Piece { kind: Knight, color: Black }), Some(Piece { kind: Rook, color: Black })],
-                [ Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black })],
-                [ None, None, None, None, None, None, None, None],
-                [ None, None, None, None, None, None, None, None],
-                [ None, None, None, None, None, None, None, None],
-                [ None, None, None, None, None, None, None, None],
-                [ Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White })],
-                [ Some(Piece { kind: Rook, color: White }), Some(Piece { kind: Knight, color: White }), Some(Piece { kind: Bishop, color: White }), Some(Piece { kind: Queen, color: White }), Some(Piece { kind: King, color: White }), Some(Piece { kind: Bishop, color: White }), Some(Piece { kind: Knight, color: White }), Some(Piece { kind: Rook, color: White })],
-            ],
-            castling: CastleState { white: (false, false), black: (false, false) },
+            squares: [None; 64],
+            kinds: [0; 6],
+            colors: [0; 2],
+            castling: CastleState { white: (true, true), black: (true, true) },
             moves_since_progress: 0,
             en_passant_file: None,
-            turn: White,
+            turn: Color::White,
+            fullmove_number: 1,
+            hash: 0,
+            history: vec![],
+            repetition_rule: RepetitionRule::Threefold,
         }
     }
+    pub fn new() -> Board {
+        use Kind::*;
+        use Color::*;
+        let positions = [
+            [ Some(Piece { kind: Rook, color: Black }) , Some(Piece { kind: Knight, color: Black }), Some(Piece { kind: Bishop, color: Black }), Some(Piece { kind: Queen, color: Black }), Some(Piece { kind: King, color: Black }), Some(Piece { kind: Bishop, color: Black }), Some(Piece { kind: Knight, color: Black }), Some(Piece { kind: Rook, color: Black })],
+            [ Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black }), Some(Piece { kind: Pawn, color: Black })],
+            [ None, None, None, None, None, None, None, None],
+            [ None, None, None, None, None, None, None, None],
+            [ None, None, None, None, None, None, None, None],
+            [ None, None, None, None, None, None, None, None],
+            [ Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White }), Some(Piece { kind: Pawn, color: White })],
+            [ Some(Piece { kind: Rook, color: White }), Some(Piece { kind: Knight, color: White }), Some(Piece { kind: Bishop, color: White }), Some(Piece { kind: Queen, color: White }), Some(Piece { kind: King, color: White }), Some(Piece { kind: Bishop, color: White }), Some(Piece { kind: Knight, color: White }), Some(Piece { kind: Rook, color: White })],
+        ];
+        let mut board = Board::empty();
+        for (row, pieces) in positions.iter().enumerate() {
+            for (file, piece) in pieces.iter().enumerate() {
+                board.put(Loc(file as isize, 7 - row as isize), *piece);
+            }
+        }
+        board.castling = CastleState { white: (false, false), black: (false, false) };
+        board.hash = board.compute_hash();
+        board
+    }
+    // The pseudo-legal moves of the piece on the square, which may leave its own king in check
     pub fn get_moves_for(&self, loc: Loc) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        self.push_moves_for(loc, !0, &mut moves);
+        moves.to_vec()
+    }
+    // Adds the pseudo-legal moves of the piece on the square that land on one of the targets. En
+    // passant counts as landing on the captured pawn too, so it can answer a check by that pawn.
+    fn push_moves_for(&self, loc: Loc, targets: Bitboard, moves: &mut MoveList) {
         use Kind::*;
-        let Some(piece) = self[loc] else { return vec![] };
+        let Some(piece) = self[loc] else { return };
         if piece.color != self.turn {
-            return vec![];
+            return;
         }
-        let mut moves = vec![];
-        match piece.kind {
+        let own = self.color_bitboard(piece.color);
+        let enemy = self.color_bitboard(piece.color.opposite());
+        let destinations = match piece.kind {
             Pawn => {
+                let mut destinations = 0;
                 // normal pawn move
                 let target = loc + piece.color.forwardv();
-                if target.is_valid() && self[target] == None {
-                    moves.push(loc + piece.color.forwardv())
-                }
-                // double pawn move
-                let target = loc + piece.color.forwardv() * 2;
-                if target.is_valid() && self[target] == None && piece.color.pawn_rank() == loc.rank() {
-                    moves.push(target)
-                }
-                // pawn attack
-                for offset in [-1, 1] {
-                    let target = loc + Loc(offset, piece.color.forward());
-                    if target.is_valid() && self[target] != None {
-                        moves.push(target)
-                    }
-                }
-            }
-            Rook => {
-                for dir in [Loc(1, 0), Loc(0, 1), Loc(-1, 0), Loc(0, -1)] {
-                    for offset in 1..7 {
-                        let target = loc + dir * offset;
-                        if !target.is_valid() || self[target].map(|x| x.color) == Some(piece.color) {
-                            break;
-                        }
-                        moves.push(target);
-                        if self[target] != None {
-                            break;
-                        }
+                if target.is_valid() && self[target].is_none() {
+                    destinations |= bit(target);
+                    // double pawn move
+                    let target = target + piece.color.forwardv();
+                    if piece.color.pawn_rank() == loc.rank() && self[target].is_none() {
+                        destinations |= bit(target);
                     }
                 }
+                // pawn attack
+                destinations | (PAWN_ATTACKS[piece.color as usize][square(loc)] & enemy)
             }
-            Knight => {
-                for long in [-2, 2] {
-                    for short in [-1, 1] {
-                        for delta in [Loc(long, short), Loc(short, long)] {
-                            let target = loc + delta;
-                            if target.is_valid() {
-                                moves.push(target);
-                            }
-                        }
-                    }
+            Rook => rook_attacks(loc, self.occupied()) & !own,
+            Knight => KNIGHT_ATTACKS[square(loc)] & !own,
+            Bishop => bishop_attacks(loc, self.occupied()) & !own,
+            Queen => queen_attacks(loc, self.occupied()) & !own,
+            King => KING_ATTACKS[square(loc)] & !own,
+        };
+        let promote_rank = piece.color.pawn_rank() + 5 * piece.color.forward();
+        for to in Squares(destinations & targets) {
+            let mv = Move {
+                player: piece.color,
+                from: loc,
+                to,
+                piece,
+                attack: self[to].map(|piece| (piece, to)),
+                promote: None,
+                castle: None
+            };
+            // promotions
+            if piece.kind == Pawn && promote_rank == loc.rank() {
+                for kind in [Rook, Knight, Bishop, Queen] {
+                    moves.push(Move { promote: Some(Piece { kind, color: piece.color }), ..mv });
                 }
             }
-            Bishop => {
-                for dir in [Loc(1, 1), Loc(1, -1), Loc(-1, -1), Loc(-1, 1)] {
-                    for offset in 1..7 {
-                        let target = loc + dir * offset;
-                        if !target.is_valid() || self[target].map(|x| x.color) == Some(piece.color) {
-                            break;
-                        }
-                        moves.push(target);
-                        if self[target] != None {
-                            break;
-                        }
-                    }
-                }
-            }
-            Queen => {
-                for dir in [Loc(1, 0), Loc(0, 1), Loc(-1, 0), Loc(0, -1), Loc(1, 1), Loc(1, -1), Loc(-1, -1), Loc(-1, 1)] {
-                    for offset in 1..7 {
-                        let target = loc + dir * offset;
-                        if !target.is_valid() || self[target].map(|x| x.color) == Some(piece.color) {
-                            break;
-                        }
-                        moves.push(target);
-                        if self[target] != None {
-                            break;
-                        }
-                    }
-                }
-            }
-            King => {
-                for dir in [Loc(1, 0), Loc(0, 1), Loc(-1, 0), Loc(0, -1), Loc(1, 1), Loc(1, -1), Loc(-1, -1), Loc(-1, 1)] {
-                    let target = loc + dir;
-                    if !target.is_valid() {
-                        continue;
-                    }
-                    moves.push(target);
-                }
+            else {
+                moves.push(mv);
             }
         }
-        let mut moves: Vec<Move> = moves.iter().map(|to| Move {
-            player: piece.color,
-            from: loc,
-            to: *to,
-            piece: piece,
-            attack: self[*to].map(|piece| (piece, *to)),
-            promote: None,
-            castle: None
-        }).filter(|mv| mv.attack.map(|(pc, _)| pc.color) != Some(self.turn)).collect();
         // special moves
         match piece.kind {
             Pawn => {
-                // promotions
-                let promote_rank = piece.color.pawn_rank() + 5 * piece.color.forward();
-                if promote_rank == loc.rank() {
-                    moves = [Rook, Knight, Bishop, Queen].iter().flat_map(|kind| moves.iter().map(|mv| {
-                        let mut mv = *mv;
-                        mv.promote = Some(Piece {
-                            kind: *kind,
-                            color: piece.color
-                        });
-                        mv
-                    })).collect();
-                }
                 // en passant
                 let en_passant_rank = piece.color.pawn_rank() + 3 * piece.color.forward();
                 if (self.en_passant_file == Some(loc.file() + 1) || self.en_passant_file == Some(loc.file() - 1)) && en_passant_rank == loc.rank() {
                     let target = Loc(self.en_passant_file.unwrap(), en_passant_rank);
-                    moves.push(Move {
-                        player: piece.color,
-                        from: loc,
-                        to: Loc(self.en_passant_file.unwrap(), loc.rank() + piece.color.forward()),
-                        piece: piece,
-                        attack: Some((self[target].unwrap(), target)),
-                        promote: None,
-                        castle: None
-                    })
+                    let to = Loc(self.en_passant_file.unwrap(), loc.rank() + piece.color.forward());
+                    if targets & (bit(target) | bit(to)) != 0 {
+                        moves.push(Move {
+                            player: piece.color,
+                            from: loc,
+                            to,
+                            piece,
+                            attack: Some((self[target].unwrap(), target)),
+                            promote: None,
+                            castle: None
+                        })
+                    }
                 }
             }
             King => {
                 // castling
-                // TODO you shouldn't be allowed to castle if you're threatened
+                let rook = Piece { kind: Rook, color: piece.color };
+                let enemy = piece.color.opposite();
                 for (rook_file, mut spaces, king_dir, castled) in [(0, 1..4, -1, self.castling[piece.color].0), (7, 5..7, 1, self.castling[piece.color].1)] {
-                    if !castled {
+                    if !castled && targets & bit(loc + Loc(2*king_dir, 0)) != 0 {
                         let rook_loc = Loc(rook_file, loc.rank());
-                        let Some(rook) = self[rook_loc] else { continue; };
-                        if spaces.all(|file| self[Loc(file, loc.rank())] == None) {
+                        if self[rook_loc] != Some(rook) {
+                            continue;
+                        }
+                        // the king may not castle out of, through or into check
+                        let king_path = [loc, loc + Loc(king_dir, 0), loc + Loc(2*king_dir, 0)];
+                        if spaces.all(|file| self[Loc(file, loc.rank())].is_none()) && !king_path.iter().any(|sq| self.is_square_attacked(*sq, enemy)) {
                             moves.push(Move {
                                 player: piece.color,
                                 from: loc,
                                 to: loc + Loc(2*king_dir, 0),
-                                piece: piece,
+                                piece,
                                 attack: None,
                                 promote: None,
                                 castle: Some(CastleMove {
-                                    rook: rook,
+                                    rook,
                                     rook_from: rook_loc,
                                     rook_to: loc + Loc(king_dir, 0)
                                 })
@@ -341,22 +351,91 @@ impl Board {
             }
             _ => {}
         }
+    }
+    // Adds the legal moves of the given stage for the side to move to the end of the list
+    pub fn generate_moves(&self, stage: Stage, moves: &mut MoveList) {
+        use Kind::*;
+        let own = self.color_bitboard(self.turn);
+        let enemy = self.color_bitboard(self.turn.opposite());
+        // narrow down where pieces other than the king may go; the stage check below does the rest
+        let targets = match stage {
+            Stage::All => !own,
+            Stage::Captures => enemy | BACK_RANKS,
+            Stage::Quiets => !self.occupied(),
+            Stage::Evasions => {
+                let Some(king) = self.king_loc(self.turn) else { return };
+                let checkers = self.attackers(king, self.turn.opposite(), self.occupied());
+                if checkers == 0 {
+                    return;
+                }
+                // a single checker can be captured, or blocked if it is a slider, but against a
+                // double check only moving the king helps
+                let checker = loc_of(checkers.trailing_zeros() as usize);
+                match self[checker].map(|piece| piece.kind) {
+                    _ if checkers.count_ones() > 1 => 0,
+                    Some(Rook | Bishop | Queen) => checkers | between(king, checker),
+                    _ => checkers,
+                }
+            }
+        };
+        let king_targets = if stage == Stage::Evasions { !own } else { targets };
+        let king = self.king_loc(self.turn);
+        let mut kept = moves.len();
+        for loc in Squares(own) {
+            let targets = if self[loc].is_some_and(|piece| piece.kind == King) { king_targets } else { targets };
+            let pushed = kept;
+            self.push_moves_for(loc, targets, moves);
+            // weed out each piece's moves right away, so the list only ever holds the legal moves so
+            // far plus one piece's worth of pseudo-legal ones, which always fits
+            for ix in pushed..moves.len() {
+                let mv = moves[ix];
+                if stage.includes(&mv) && self.leaves_king_safe(&mv, king) {
+                    moves[kept] = mv;
+                    kept += 1;
+                }
+            }
+            moves.truncate(kept);
+        }
+    }
+    pub fn all_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        self.generate_moves(Stage::All, &mut moves);
         moves
     }
+    // Places a piece on (or clears) a square, keeping the bitboards and hash up to date
+    pub fn put(&mut self, loc: Loc, piece: Option<Piece>) {
+        if let Some(old) = self[loc] {
+            self.hash ^= zobrist::piece_key(old, loc);
+            self.kinds[old.kind as usize] &= !bit(loc);
+            self.colors[old.color as usize] &= !bit(loc);
+        }
+        if let Some(new) = piece {
+            self.hash ^= zobrist::piece_key(new, loc);
+            self.kinds[new.kind as usize] |= bit(loc);
+            self.colors[new.color as usize] |= bit(loc);
+        }
+        self.squares[square(loc)] = piece;
+    }
+    fn hash_state(&mut self) {
+        self.hash ^= zobrist::castling_key(&self.castling) ^ zobrist::en_passant_key(self.en_passant_file) ^ zobrist::turn_key(self.turn);
+    }
     pub fn apply_move(&mut self, mv: Move) {
+        self.history.push(self.hash);
+        // the castling rights, en passant file and turn are hashed back in once they have been updated
+        self.hash_state();
         let mut piece = self[mv.from].unwrap();
-        self[mv.from] = None;
+        self.put(mv.from, None);
         if let Some((_, position)) = mv.attack {
-            self[position] = None;
+            self.put(position, None);
         }
         if let Some(promotion) = mv.promote {
             piece = promotion;
         }
-        self[mv.to] = Some(piece);
+        self.put(mv.to, Some(piece));
         if let Some(castle_move) = mv.castle {
             let rook = self[castle_move.rook_from].unwrap();
-            self[castle_move.rook_from] = None;
-            self[castle_move.rook_to] = Some(rook);
+            self.put(castle_move.rook_from, None);
+            self.put(castle_move.rook_to, Some(rook));
         }
         if piece.kind == Kind::King || (piece.kind == Kind::Rook && mv.from == Loc(0, piece.color.back_rank())) {
             self.castling[piece.color].0 = true;
@@ -364,52 +443,250 @@ impl Board {
         if piece.kind == Kind::King || (piece.kind == Kind::Rook && mv.from == Loc(7, piece.color.back_rank())) {
             self.castling[piece.color].1 = true;
         }
+        // capturing a rook on its home square takes away the opponent's right to castle with it
+        if let Some((Piece { kind: Kind::Rook, color }, position)) = mv.attack {
+            if position == Loc(0, color.back_rank()) {
+                self.castling[color].0 = true;
+            }
+            if position == Loc(7, color.back_rank()) {
+                self.castling[color].1 = true;
+            }
+        }
         if piece.kind == Kind::Pawn && (mv.from.rank() - mv.to.rank()).abs() == 2 {
             self.en_passant_file = Some(mv.to.file());
         }
         else {
             self.en_passant_file = None;
         }
+        if self.turn == Color::Black {
+            self.fullmove_number += 1;
+        }
         self.turn = self.turn.opposite();
-        if mv.attack == None && mv.piece.kind != Kind::Pawn && mv.castle == None {
+        self.hash_state();
+        if mv.attack.is_none() && mv.piece.kind != Kind::Pawn && mv.castle.is_none() {
             self.moves_since_progress += 1;
         }
         else {
             self.moves_since_progress = 0;
         }
     }
-    pub fn consider_move<T>(&mut self, mv: Move, f: impl FnOnce(&mut Board) -> T) -> T {
-        let prev_castling = self.castling;
-        let prev_en_passant = self.en_passant_file;
-        let prev_progress = self.moves_since_progress;
+    fn undo(&self) -> Undo {
+        Undo {
+            castling: self.castling,
+            en_passant_file: self.en_passant_file,
+            moves_since_progress: self.moves_since_progress,
+            hash: self.hash,
+        }
+    }
+    // Plays a move, returning what unmake_move needs to take it back again
+    pub fn make_move(&mut self, mv: Move) -> Undo {
+        let undo = self.undo();
         self.apply_move(mv);
-        let res = f(self);
-        // Undo move
+        undo
+    }
+    // Takes back a move played by make_move, which must be the last move played
+    pub fn unmake_move(&mut self, mv: Move, undo: Undo) {
         if let Some(castle_move) = mv.castle {
-            self[castle_move.rook_to] = None;
-            self[castle_move.rook_from] = Some(castle_move.rook);
+            self.put(castle_move.rook_to, None);
+            self.put(castle_move.rook_from, Some(castle_move.rook));
         }
-        self[mv.from] = Some(mv.piece);
-        self[mv.to] = None;
+        self.put(mv.to, None);
+        self.put(mv.from, Some(mv.piece));
         if let Some((attacked, loc)) = mv.attack {
-            self[loc] = Some(attacked);
+            self.put(loc, Some(attacked));
         }
-        self.castling = prev_castling;
-        self.en_passant_file = prev_en_passant;
-        self.moves_since_progress = prev_progress;
+        self.castling = undo.castling;
+        self.en_passant_file = undo.en_passant_file;
+        self.moves_since_progress = undo.moves_since_progress;
+        self.hash = undo.hash;
+        self.history.pop();
         self.turn = self.turn.opposite();
+        if self.turn == Color::Black {
+            self.fullmove_number -= 1;
+        }
+    }
+    // Passes the turn without moving, which search uses to see how strong a position is even when
+    // giving the opponent a free move. Since that isn't a legal move, positions before it don't
+    // count towards repetitions.
+    pub fn make_null_move(&mut self) -> Undo {
+        let undo = self.undo();
+        self.history.push(self.hash);
+        self.hash_state();
+        self.en_passant_file = None;
+        self.turn = self.turn.opposite();
+        self.hash_state();
+        self.moves_since_progress = 0;
+        undo
+    }
+    pub fn unmake_null_move(&mut self, undo: Undo) {
+        self.castling = undo.castling;
+        self.en_passant_file = undo.en_passant_file;
+        self.moves_since_progress = undo.moves_since_progress;
+        self.hash = undo.hash;
+        self.history.pop();
+        self.turn = self.turn.opposite();
+    }
+    pub fn consider_move<T>(&mut self, mv: Move, f: impl FnOnce(&mut Board) -> T) -> T {
+        let undo = self.make_move(mv);
+        let res = f(self);
+        self.unmake_move(mv, undo);
         res
     }
-    pub fn win_state(&self) -> WinState {
-        if self.moves_since_progress >= 50 {
-            WinState::Draw
+    pub fn king_loc(&self, color: Color) -> Option<Loc> {
+        Squares(self.piece_bitboard(Piece { kind: Kind::King, color })).next()
+    }
+    // The pieces of the given color that attack the square, if the occupied squares were as given
+    pub fn attackers(&self, loc: Loc, by: Color, occupied: Bitboard) -> Bitboard {
+        use Kind::*;
+        let sq = square(loc);
+        let theirs = |kind: Kind| self.piece_bitboard(Piece { kind, color: by });
+        // pawns attack diagonally forward, so look diagonally backward from the target
+        (PAWN_ATTACKS[by.opposite() as usize][sq] & theirs(Pawn))
+            | (KNIGHT_ATTACKS[sq] & theirs(Knight))
+            | (KING_ATTACKS[sq] & theirs(King))
+            | (rook_attacks(loc, occupied) & (theirs(Rook) | theirs(Queen)))
+            | (bishop_attacks(loc, occupied) & (theirs(Bishop) | theirs(Queen)))
+    }
+    // Whether any piece of the given color could capture on the given square
+    pub fn is_square_attacked(&self, loc: Loc, by: Color) -> bool {
+        self.attackers(loc, by, self.occupied()) != 0
+    }
+    pub fn is_in_check(&self, color: Color) -> bool {
+        self.king_loc(color).is_some_and(|king| self.is_square_attacked(king, color.opposite()))
+    }
+    // Whether a pseudo-legal move keeps the mover's king (found on the given square before the move)
+    // out of check, worked out from the bitboards without playing it. Castling checks its own path when it is generated.
+    fn leaves_king_safe(&self, mv: &Move, king: Option<Loc>) -> bool {
+        let mut occupied = (self.occupied() & !bit(mv.from)) | bit(mv.to);
+        let mut captured = 0;
+        if let Some((_, loc)) = mv.attack {
+            occupied &= !bit(loc);
+            occupied |= bit(mv.to);
+            captured = bit(loc);
         }
-        // TODO right now I'm just letting winning = taking the opponents' king, but really we should implement the checkmating nonsense
-        else if !(0..8).any(|x| (0..8).any(|y| self[Loc(x, y)] == Some(Piece {kind: Kind::King, color:self.turn}))) {
+        let king = match king {
+            _ if mv.piece.kind == Kind::King => mv.to,
+            Some(king) => king,
+            None => return true,
+        };
+        self.attackers(king, mv.player.opposite(), occupied) & !captured == 0
+    }
+    // Like get_moves_for, but without the moves that would leave the mover's king in check
+    pub fn legal_moves_for(&self, loc: Loc) -> Vec<Move> {
+        self.all_moves().iter().filter(|mv| mv.from == loc).copied().collect()
+    }
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.all_moves().to_vec()
+    }
+    // How many times the current position has occurred before. Only positions since the last capture
+    // or pawn move can match, and only every other one has the same side to move.
+    pub fn repetitions(&self) -> usize {
+        self.history.iter().rev().take(self.moves_since_progress).skip(1).step_by(2).filter(|hash| **hash == self.hash).count()
+    }
+    // Whether the current position has occurred before, which search can treat as a draw
+    pub fn is_repetition(&self) -> bool {
+        self.repetitions() > 0
+    }
+    // Whether neither side has enough material left to ever checkmate: bare kings, a single minor
+    // piece, or only bishops that all stand on squares of the same color
+    pub fn has_insufficient_material(&self) -> bool {
+        let pieces = self.occupied() & !self.kind_bitboard(Kind::King);
+        let minors = self.kind_bitboard(Kind::Knight) | self.kind_bitboard(Kind::Bishop);
+        let bishops = self.kind_bitboard(Kind::Bishop);
+        (pieces.count_ones() <= 1 && pieces & !minors == 0)
+            || (pieces == bishops && (pieces & LIGHT_SQUARES == 0 || pieces & !LIGHT_SQUARES == 0))
+    }
+    // Whether the game is drawn by a rule that doesn't depend on the moves available: the
+    // fifty-move rule, repetition or insufficient material. Search checks this at every node and
+    // finds checkmate and stalemate from the moves it generates anyway.
+    pub fn is_drawn_by_rule(&self) -> bool {
+        let repetition_limit = match self.repetition_rule {
+            RepetitionRule::Threefold => 3,
+            RepetitionRule::Fivefold => 5,
+        };
+        // the clock counts plies, so the fifty-move rule kicks in at 100
+        self.moves_since_progress >= 100 || self.repetitions() + 1 >= repetition_limit || self.has_insufficient_material()
+    }
+    pub fn win_state(&self) -> WinState {
+        let no_moves = self.all_moves().is_empty();
+        // checkmate ends the game before any draw rule gets a say
+        if no_moves && self.is_in_check(self.turn) {
             WinState::Winner(self.turn.opposite())
         }
+        else if no_moves || self.is_drawn_by_rule() {
+            WinState::Draw
+        }
         else {
             WinState::Playing
         }
     }
-}
\ No newline at end of file
+}
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifty_move_rule_counts_plies() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 60 80").unwrap();
+        assert_eq!(board.win_state(), WinState::Playing);
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert_eq!(board.win_state(), WinState::Playing);
+        board.apply_move(board.parse_uci_move("a1a2").unwrap());
+        assert_eq!(board.win_state(), WinState::Draw);
+    }
+
+    #[test]
+    fn checkmate_beats_the_fifty_move_rule() {
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+        board.apply_move(board.parse_uci_move("a1a8").unwrap());
+        assert_eq!(board.moves_since_progress, 100);
+        assert_eq!(board.win_state(), WinState::Winner(Color::White));
+    }
+
+    // Plays knights out and back, returning to the starting position every four plies, and returns
+    // the ply at which the game was first drawn
+    fn shuffle_knights_until_drawn(rule: RepetitionRule) -> usize {
+        let mut board = Board::new();
+        board.repetition_rule = rule;
+        for (ply, text) in ["g1f3", "g8f6", "f3g1", "f6g8"].iter().cycle().enumerate() {
+            board.apply_move(board.parse_uci_move(text).unwrap());
+            if board.win_state() == WinState::Draw {
+                return ply + 1;
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        // the starting position occurs for the third time after eight plies
+        assert_eq!(shuffle_knights_until_drawn(RepetitionRule::Threefold), 8);
+    }
+
+    #[test]
+    fn fivefold_repetition() {
+        assert_eq!(shuffle_knights_until_drawn(RepetitionRule::Fivefold), 16);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let drawn = |fen: &str| Board::from_fen(fen).unwrap().has_insufficient_material();
+        assert!(drawn("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(drawn("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1"));
+        assert!(drawn("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        // c1 and f8 are both dark squares
+        assert!(drawn("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert_eq!(Board::from_fen("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap().win_state(), WinState::Draw);
+        assert!(!drawn("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+        // c1 is dark and c8 is light
+        assert!(!drawn("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!drawn("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1"));
+        assert!(!drawn("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+    }
+}
@@ -0,0 +1,96 @@
+use super::*;
+
+// A set of squares, with bit (rank * 8 + file) standing for the square Loc(file, rank)
+pub type Bitboard = u64;
+
+pub const LIGHT_SQUARES: Bitboard = 0x55aa55aa55aa55aa;
+// The first and eighth ranks, where pawns promote
+pub const BACK_RANKS: Bitboard = 0xff000000000000ff;
+
+pub fn square(loc: Loc) -> usize {
+    (loc.rank() * 8 + loc.file()) as usize
+}
+pub fn loc_of(square: usize) -> Loc {
+    Loc((square % 8) as isize, (square / 8) as isize)
+}
+pub fn bit(loc: Loc) -> Bitboard {
+    1 << square(loc)
+}
+
+// The squares strictly between two squares that share a rank, file or diagonal
+pub fn between(from: Loc, to: Loc) -> Bitboard {
+    let step = Loc((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let mut squares = 0;
+    let mut loc = from + step;
+    while loc != to {
+        squares |= bit(loc);
+        loc = loc + step;
+    }
+    squares
+}
+
+// Iterates over the squares in a bitboard, from a1 to h8
+pub struct Squares(pub Bitboard);
+impl Iterator for Squares {
+    type Item = Loc;
+    fn next(&mut self) -> Option<Loc> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(loc_of(square))
+    }
+}
+
+// For pieces that jump straight to their targets, the squares they attack from each square
+const fn leaper_table(deltas: &[(isize, isize)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let (file, rank) = ((square % 8) as isize, (square / 8) as isize);
+        let mut i = 0;
+        while i < deltas.len() {
+            let (to_file, to_rank) = (file + deltas[i].0, rank + deltas[i].1);
+            if to_file >= 0 && to_file < 8 && to_rank >= 0 && to_rank < 8 {
+                table[square] |= 1 << (to_rank * 8 + to_file);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_table(&[(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)]);
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_table(&[(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)]);
+// Indexed by the color of the pawn
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [leaper_table(&[(-1, 1), (1, 1)]), leaper_table(&[(-1, -1), (1, -1)])];
+
+impl Board {
+    pub fn kind_bitboard(&self, kind: Kind) -> Bitboard {
+        self.kinds[kind as usize]
+    }
+    pub fn color_bitboard(&self, color: Color) -> Bitboard {
+        self.colors[color as usize]
+    }
+    pub fn piece_bitboard(&self, piece: Piece) -> Bitboard {
+        self.kind_bitboard(piece.kind) & self.color_bitboard(piece.color)
+    }
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+    // The squares the piece on the square attacks, whether or not it could legally move there
+    pub fn attacks_from(&self, loc: Loc) -> Bitboard {
+        use Kind::*;
+        let Some(piece) = self[loc] else { return 0 };
+        match piece.kind {
+            Pawn => PAWN_ATTACKS[piece.color as usize][square(loc)],
+            Rook => rook_attacks(loc, self.occupied()),
+            Knight => KNIGHT_ATTACKS[square(loc)],
+            Bishop => bishop_attacks(loc, self.occupied()),
+            Queen => queen_attacks(loc, self.occupied()),
+            King => KING_ATTACKS[square(loc)],
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use super::*;
+
+// Forsyth-Edwards Notation, e.g. "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FenError {
+    MissingField(&'static str),
+    TrailingFields(String),
+    BadPlacement(String),
+    KingCount(Color, u32),
+    TooManyPieces(Color),
+    BadTurn(String),
+    OpponentInCheck(Color),
+    BadCastling(String),
+    BadEnPassant(String),
+    BadCounter(String),
+}
+impl std::fmt::Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use FenError::*;
+        match self {
+            MissingField(field) => write!(f, "missing {} field", field),
+            TrailingFields(rest) => write!(f, "unexpected trailing fields: '{}'", rest),
+            BadPlacement(placement) => write!(f, "invalid piece placement: '{}'", placement),
+            KingCount(color, count) => write!(f, "{} has {} kings instead of one", color, count),
+            TooManyPieces(color) => write!(f, "{} has more pieces than it could have promoted to", color),
+            BadTurn(turn) => write!(f, "invalid side to move: '{}'", turn),
+            OpponentInCheck(color) => write!(f, "{} is in check, but it isn't their move", color),
+            BadCastling(castling) => write!(f, "invalid castling rights: '{}'", castling),
+            BadEnPassant(square) => write!(f, "invalid en passant square: '{}'", square),
+            BadCounter(counter) => write!(f, "invalid move counter: '{}'", counter),
+        }
+    }
+}
+impl std::error::Error for FenError {}
+
+fn piece_from_char(ch: char) -> Option<Piece> {
+    use Kind::*;
+    let kind = match ch.to_ascii_lowercase() {
+        'p' => Pawn,
+        'r' => Rook,
+        'n' => Knight,
+        'b' => Bishop,
+        'q' => Queen,
+        'k' => King,
+        _ => return None,
+    };
+    let color = if ch.is_ascii_uppercase() { Color::White } else { Color::Black };
+    Some(Piece { kind, color })
+}
+fn piece_to_char(piece: Piece) -> char {
+    use Kind::*;
+    let ch = match piece.kind {
+        Pawn => 'p',
+        Rook => 'r',
+        Knight => 'n',
+        Bishop => 'b',
+        Queen => 'q',
+        King => 'k',
+    };
+    match piece.color {
+        Color::White => ch.to_ascii_uppercase(),
+        Color::Black => ch,
+    }
+}
+
+impl Board {
+    pub const STARTING_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    // The halfmove clock and fullmove number may be left out, in which case they default to 0 and 1
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(FenError::MissingField("piece placement"))?;
+        let turn = fields.next().ok_or(FenError::MissingField("side to move"))?;
+        let castling = fields.next().ok_or(FenError::MissingField("castling"))?;
+        let en_passant = fields.next().ok_or(FenError::MissingField("en passant"))?;
+        let halfmove = fields.next().unwrap_or("0");
+        let fullmove = fields.next().unwrap_or("1");
+        let rest = fields.collect::<Vec<_>>();
+        if !rest.is_empty() {
+            return Err(FenError::TrailingFields(rest.join(" ")));
+        }
+
+        let mut board = Board::empty();
+
+        let bad_placement = || FenError::BadPlacement(placement.into());
+        let ranks = placement.split('/').collect::<Vec<_>>();
+        if ranks.len() != 8 {
+            return Err(bad_placement());
+        }
+        for (row, rank) in ranks.iter().enumerate() {
+            let y = 7 - row as isize;
+            let mut x = 0;
+            for ch in rank.chars() {
+                if let Some(skip) = ch.to_digit(10) {
+                    if !(1..=8).contains(&skip) {
+                        return Err(bad_placement());
+                    }
+                    x += skip as isize;
+                }
+                else {
+                    let piece = piece_from_char(ch).ok_or_else(bad_placement)?;
+                    if x >= 8 {
+                        return Err(bad_placement());
+                    }
+                    board.put(Loc(x, y), Some(piece));
+                    x += 1;
+                }
+            }
+            if x != 8 {
+                return Err(bad_placement());
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let kings = board.piece_bitboard(Piece { kind: Kind::King, color }).count_ones();
+            if kings != 1 {
+                return Err(FenError::KingCount(color, kings));
+            }
+            // every piece beyond the starting set must have been a pawn, which also keeps it to 16
+            // pieces and 8 pawns, and move generation relies on that to fit the moves in a MoveList
+            let count = |kind: Kind| board.piece_bitboard(Piece { kind, color }).count_ones();
+            let promoted = count(Kind::Queen).saturating_sub(1) + count(Kind::Rook).saturating_sub(2)
+                + count(Kind::Bishop).saturating_sub(2) + count(Kind::Knight).saturating_sub(2);
+            if count(Kind::Pawn) + promoted > 8 {
+                return Err(FenError::TooManyPieces(color));
+            }
+        }
+
+        board.turn = match turn {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::BadTurn(turn.into())),
+        };
+        // the side that just moved can't have left its king in check
+        if board.is_in_check(board.turn.opposite()) {
+            return Err(FenError::OpponentInCheck(board.turn.opposite()));
+        }
+
+        if castling != "-" {
+            for ch in castling.chars() {
+                let (color, rook_file) = match ch {
+                    'K' => (Color::White, 7),
+                    'Q' => (Color::White, 0),
+                    'k' => (Color::Black, 7),
+                    'q' => (Color::Black, 0),
+                    _ => return Err(FenError::BadCastling(castling.into())),
+                };
+                // a right to castle means neither the king nor that rook has moved
+                let home_rank = match color {
+                    Color::White => 0,
+                    Color::Black => 7,
+                };
+                if board[Loc(4, home_rank)] != Some(Piece { kind: Kind::King, color }) || board[Loc(rook_file, home_rank)] != Some(Piece { kind: Kind::Rook, color }) {
+                    return Err(FenError::BadCastling(castling.into()));
+                }
+                match rook_file {
+                    0 => board.castling[color].0 = false,
+                    _ => board.castling[color].1 = false,
+                }
+            }
+        }
+
+        if en_passant != "-" {
+            let bad_en_passant = || FenError::BadEnPassant(en_passant.into());
+            let mut chars = en_passant.chars();
+            let (Some(file), Some(rank), None) = (chars.next(), chars.next(), chars.next()) else {
+                return Err(bad_en_passant());
+            };
+            // the target square is the one the pawn skipped over, so it depends on who moved
+            let expected_rank = match board.turn {
+                Color::White => '6',
+                Color::Black => '3',
+            };
+            if !('a'..='h').contains(&file) || rank != expected_rank {
+                return Err(bad_en_passant());
+            }
+            // the pawn that just moved two squares stands right behind the target, and the squares
+            // it passed over are empty
+            let x = file as isize - 'a' as isize;
+            let (target, from, to) = match board.turn {
+                Color::White => (Loc(x, 5), Loc(x, 6), Loc(x, 4)),
+                Color::Black => (Loc(x, 2), Loc(x, 1), Loc(x, 3)),
+            };
+            let pawn = Piece { kind: Kind::Pawn, color: board.turn.opposite() };
+            if board[target].is_some() || board[from].is_some() || board[to] != Some(pawn) {
+                return Err(bad_en_passant());
+            }
+            board.en_passant_file = Some(x);
+        }
+
+        board.moves_since_progress = halfmove.parse().map_err(|_| FenError::BadCounter(halfmove.into()))?;
+        board.fullmove_number = fullmove.parse().map_err(|_| FenError::BadCounter(fullmove.into()))?;
+        board.hash = board.compute_hash();
+        Ok(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for y in (0..8).rev() {
+            let mut empty = 0;
+            for x in 0..8 {
+                match self[Loc(x, y)] {
+                    None => empty += 1,
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_to_char(piece));
+                    }
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if y > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.turn {
+            Color::White => " w ",
+            Color::Black => " b ",
+        });
+
+        let rights = [(self.castling.white.1, 'K'), (self.castling.white.0, 'Q'), (self.castling.black.1, 'k'), (self.castling.black.0, 'q')];
+        let castling = rights.iter().filter(|(lost, _)| !lost).map(|(_, ch)| *ch).collect::<String>();
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        match self.en_passant_file {
+            None => fen.push_str(" -"),
+            Some(file) => {
+                let rank = match self.turn {
+                    Color::White => '6',
+                    Color::Black => '3',
+                };
+                fen.push(' ');
+                fen.push((b'a' + file as u8) as char);
+                fen.push(rank);
+            }
+        }
+
+        fen.push_str(&format!(" {} {}", self.moves_since_progress, self.fullmove_number));
+        fen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        for fen in [
+            Board::STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/5N2/PPP1PPPP/RNBQKB1R b Kq d3 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 37 60",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+        assert_eq!(Board::from_fen(Board::STARTING_FEN).unwrap(), Board::new());
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - -").unwrap().to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    }
+
+    #[test]
+    fn rejects_bad_fields() {
+        use FenError::*;
+        let error = |fen: &str| Board::from_fen(fen).unwrap_err();
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w -"), MissingField("en passant"));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - 0 1 extra"), TrailingFields("extra".into()));
+        assert_eq!(error("4k3/8/8/8/8/8/4K3 w - - 0 1"), BadPlacement("4k3/8/8/8/8/8/4K3".into()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K4 w - - 0 1"), BadPlacement("4k3/8/8/8/8/8/8/4K4".into()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4X3 w - - 0 1"), BadPlacement("4k3/8/8/8/8/8/8/4X3".into()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 x - - 0 1"), BadTurn("x".into()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - x 1"), BadCounter("x".into()));
+    }
+
+    #[test]
+    fn rejects_wrong_king_counts() {
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").unwrap_err(), FenError::KingCount(Color::Black, 0));
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1").unwrap_err(), FenError::KingCount(Color::White, 2));
+    }
+
+    #[test]
+    fn rejects_the_side_not_to_move_in_check() {
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/R3K2r b - - 0 1").unwrap_err(), FenError::OpponentInCheck(Color::White));
+        // the same position is fine with White to move, answering the check
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/R3K2r w - - 0 1").is_ok());
+    }
+
+    #[test]
+    fn rejects_more_pieces_than_promotions_allow() {
+        let error = |fen: &str| Board::from_fen(fen).unwrap_err();
+        assert_eq!(error("kQQQ1QQQ/Q3Q3/2Q3Q1/1Q4Q1/1Q5Q/1Q5Q/Q6Q/BQQQQQQK w - - 0 1"), FenError::TooManyPieces(Color::White));
+        assert_eq!(error("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1"), FenError::TooManyPieces(Color::Black));
+        assert_eq!(error("4k3/8/8/8/8/8/PQQ5/QQQQKQQQ w - - 0 1"), FenError::TooManyPieces(Color::White));
+        // the most moves any legal position has, with every white pawn promoted
+        let most_moves = Board::from_fen("R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1").unwrap();
+        assert_eq!(most_moves.all_moves().len(), 218);
+    }
+
+    #[test]
+    fn rejects_castling_without_king_and_rook() {
+        let error = |castling: &str| FenError::BadCastling(castling.into());
+        assert_eq!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkx - 0 1").unwrap_err(), error("KQkx"));
+        // the rook isn't on h1
+        assert_eq!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K1R1 w K - 0 1").unwrap_err(), error("K"));
+        // the king isn't on e8
+        assert_eq!(Board::from_fen("r2k3r/8/8/8/8/8/8/R3K2R w q - 0 1").unwrap_err(), error("q"));
+    }
+
+    #[test]
+    fn rejects_en_passant_without_a_pawn_that_just_moved() {
+        let error = |square: &str| FenError::BadEnPassant(square.into());
+        assert_eq!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d3 0 1").unwrap_err(), error("d3"));
+        assert_eq!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d66 0 1").unwrap_err(), error("d66"));
+        // nothing stands behind the square, or it isn't an enemy pawn
+        assert_eq!(Board::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1").unwrap_err(), error("e6"));
+        assert_eq!(Board::from_fen("4k3/8/8/3nP3/8/8/8/4K3 w - d6 0 1").unwrap_err(), error("d6"));
+        assert_eq!(Board::from_fen("4k3/8/8/3PP3/8/8/8/4K3 w - d6 0 1").unwrap_err(), error("d6"));
+        // the pawn couldn't have passed over an occupied square
+        assert_eq!(Board::from_fen("4k3/8/3b4/3pP3/8/8/8/4K3 w - d6 0 1").unwrap_err(), error("d6"));
+        assert!(Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").is_ok());
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::OnceLock;
+use super::*;
+
+// Sliding piece attacks through magic bitboards. For each square, the occupancy of the squares that
+// can block the slider is multiplied by a magic number so the top bits form a perfect hash of it,
+// which indexes a table of precomputed attack sets. Every square uses the same number of index
+// bits, which wastes some memory but makes magic numbers quick to find when the tables are built.
+
+const ROOK_DIRS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const BISHOP_DIRS: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const ROOK_BITS: u32 = 12;
+const BISHOP_BITS: u32 = 9;
+
+// Walks each ray from the square until it leaves the board or hits a piece, which is included
+fn ray_attacks(loc: Loc, occupied: Bitboard, dirs: &[(isize, isize)]) -> Bitboard {
+    let mut attacks = 0;
+    for (dx, dy) in dirs {
+        for offset in 1..8 {
+            let target = loc + Loc(*dx, *dy) * offset;
+            if !target.is_valid() {
+                break;
+            }
+            attacks |= bit(target);
+            if occupied & bit(target) != 0 {
+                break;
+            }
+        }
+    }
+    attacks
+}
+
+// The squares whose occupancy matters: the rays without their final square, since a piece there
+// can't block anything further
+fn relevant_mask(loc: Loc, dirs: &[(isize, isize)]) -> Bitboard {
+    let mut mask = 0;
+    for (dx, dy) in dirs {
+        for offset in 1..8 {
+            let target = loc + Loc(*dx, *dy) * offset;
+            if !(target + Loc(*dx, *dy)).is_valid() {
+                break;
+            }
+            mask |= bit(target);
+        }
+    }
+    mask
+}
+
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    attacks: Vec<Bitboard>,
+}
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct Tables {
+    rook: Vec<Magic>,
+    bishop: Vec<Magic>,
+}
+
+// xorshift64, seeded so the same magic numbers are found every run
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+fn find_magic(loc: Loc, dirs: &[(isize, isize)], bits: u32, state: &mut u64) -> Magic {
+    let mask = relevant_mask(loc, dirs);
+    // every subset of the mask, enumerated with the carry-rippler trick
+    let mut occupancies = vec![];
+    let mut subset: Bitboard = 0;
+    loop {
+        occupancies.push((subset, ray_attacks(loc, subset, dirs)));
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            break;
+        }
+    }
+    let shift = 64 - bits;
+    let mut attacks = vec![0; 1 << bits];
+    // which attempt last wrote each slot, so the table needn't be cleared between attempts
+    let mut written = vec![0; 1 << bits];
+    for attempt in 1.. {
+        // numbers with few bits set make better magics
+        let magic = next_random(state) & next_random(state) & next_random(state);
+        let candidate = Magic { mask, magic, shift, attacks: vec![] };
+        let fits = occupancies.iter().all(|(occupied, attacked)| {
+            let index = candidate.index(*occupied);
+            if written[index] == attempt {
+                attacks[index] == *attacked
+            }
+            else {
+                written[index] = attempt;
+                attacks[index] = *attacked;
+                true
+            }
+        });
+        if fits {
+            return Magic { attacks, ..candidate };
+        }
+    }
+    unreachable!()
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut state = 0x6b6e69676874735f;
+        Tables {
+            rook: (0..64).map(|sq| find_magic(loc_of(sq), &ROOK_DIRS, ROOK_BITS, &mut state)).collect(),
+            bishop: (0..64).map(|sq| find_magic(loc_of(sq), &BISHOP_DIRS, BISHOP_BITS, &mut state)).collect(),
+        }
+    })
+}
+
+pub fn rook_attacks(loc: Loc, occupied: Bitboard) -> Bitboard {
+    let magic = &tables().rook[square(loc)];
+    magic.attacks[magic.index(occupied)]
+}
+pub fn bishop_attacks(loc: Loc, occupied: Bitboard) -> Bitboard {
+    let magic = &tables().bishop[square(loc)];
+    magic.attacks[magic.index(occupied)]
+}
+pub fn queen_attacks(loc: Loc, occupied: Bitboard) -> Bitboard {
+    rook_attacks(loc, occupied) | bishop_attacks(loc, occupied)
+}
@@ -0,0 +1,99 @@
+use std::ops::{Deref, DerefMut};
+use super::*;
+
+// No legal position has more moves than this (the record is 218), with room to spare for the
+// pseudo-legal moves of one more piece while generating them
+pub const MAX_MOVES: usize = 256;
+
+// Fills the unused part of a MoveList
+const NO_MOVE: Move = Move {
+    player: Color::White,
+    from: Loc(0, 0),
+    to: Loc(0, 0),
+    piece: Piece { kind: Kind::Pawn, color: Color::White },
+    attack: None,
+    promote: None,
+    castle: None,
+};
+
+// A fixed-capacity list of moves, so generating moves doesn't need to allocate. It derefs to a
+// slice of the moves it holds. Setting up a new one means filling in all MAX_MOVES entries, so
+// recursive searches keep one list per ply and reuse it instead.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList { moves: [NO_MOVE; MAX_MOVES], len: 0 }
+    }
+    pub fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+}
+impl Default for MoveList {
+    fn default() -> MoveList {
+        MoveList::new()
+    }
+}
+impl Deref for MoveList {
+    type Target = [Move];
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Move, MAX_MOVES>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter().take(self.len)
+    }
+}
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Which moves to generate, so search can look at the most forcing ones first or only
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Stage {
+    All,
+    // Captures (including en passant) and promotions
+    Captures,
+    // Everything that isn't in Captures
+    Quiets,
+    // Every move, if the side to move is in check, and none otherwise
+    Evasions,
+}
+impl Stage {
+    pub fn includes(self, mv: &Move) -> bool {
+        let capture = mv.attack.is_some() || mv.promote.is_some();
+        match self {
+            Stage::All | Stage::Evasions => true,
+            Stage::Captures => capture,
+            Stage::Quiets => !capture,
+        }
+    }
+}
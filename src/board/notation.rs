@@ -0,0 +1,201 @@
+use super::*;
+
+pub fn square_name(loc: Loc) -> String {
+    format!("{}{}", (b'a' + loc.file() as u8) as char, loc.rank() + 1)
+}
+pub fn parse_square(name: &str) -> Option<Loc> {
+    let mut chars = name.chars();
+    let (Some(file), Some(rank), None) = (chars.next(), chars.next(), chars.next()) else { return None };
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return None;
+    }
+    Some(Loc(file as isize - 'a' as isize, rank as isize - '1' as isize))
+}
+fn kind_char(kind: Kind) -> char {
+    use Kind::*;
+    match kind {
+        Pawn => 'p',
+        Rook => 'r',
+        Knight => 'n',
+        Bishop => 'b',
+        Queen => 'q',
+        King => 'k',
+    }
+}
+
+fn piece_letter(kind: Kind) -> char {
+    kind_char(kind).to_ascii_uppercase()
+}
+
+impl Move {
+    // Standard algebraic notation, e.g. "e4", "Nbd2", "exd6", "O-O", "e8=Q+" or "Qxf7#".
+    // The board is the position the move is played from.
+    pub fn to_san(&self, board: &Board) -> String {
+        let mut text = self.san_without_suffix(board);
+        let mut after = board.clone();
+        after.apply_move(*self);
+        if after.is_in_check(after.turn) {
+            text.push(if after.legal_moves().is_empty() { '#' } else { '+' });
+        }
+        text
+    }
+    fn san_without_suffix(&self, board: &Board) -> String {
+        let mut text = String::new();
+        if let Some(castle) = self.castle {
+            text.push_str(if castle.rook_from.file() < self.from.file() { "O-O-O" } else { "O-O" });
+            return text;
+        }
+        if self.piece.kind == Kind::Pawn {
+            if self.attack.is_some() {
+                text.push(square_name(self.from).remove(0));
+            }
+        }
+        else {
+            text.push(piece_letter(self.piece.kind));
+            // name just enough of the origin square to tell apart identical pieces reaching the same square
+            let rivals = board.legal_moves().into_iter()
+                .filter(|mv| mv.piece == self.piece && mv.to == self.to && mv.from != self.from)
+                .collect::<Vec<_>>();
+            if !rivals.is_empty() {
+                let from = square_name(self.from);
+                if rivals.iter().all(|mv| mv.from.file() != self.from.file()) {
+                    text.push_str(&from[0..1]);
+                }
+                else if rivals.iter().all(|mv| mv.from.rank() != self.from.rank()) {
+                    text.push_str(&from[1..2]);
+                }
+                else {
+                    text.push_str(&from);
+                }
+            }
+        }
+        if self.attack.is_some() {
+            text.push('x');
+        }
+        text.push_str(&square_name(self.to));
+        if let Some(promotion) = self.promote {
+            text.push('=');
+            text.push(piece_letter(promotion.kind));
+        }
+        text
+    }
+    // Long algebraic notation as used by UCI, e.g. "e2e4", "e1g1" or "e7e8q"
+    pub fn to_uci(&self) -> String {
+        let mut text = square_name(self.from) + &square_name(self.to);
+        if let Some(promotion) = self.promote {
+            text.push(kind_char(promotion.kind));
+        }
+        text
+    }
+}
+
+impl Board {
+    // Finds the legal move matching the given long algebraic notation
+    pub fn parse_uci_move(&self, text: &str) -> Option<Move> {
+        if text.len() != 4 && text.len() != 5 {
+            return None;
+        }
+        let from = parse_square(text.get(0..2)?)?;
+        let to = parse_square(text.get(2..4)?)?;
+        let promotion = text.get(4..).and_then(|rest| rest.chars().next());
+        self.legal_moves_for(from).into_iter().find(|mv| {
+            mv.to == to && mv.promote.map(|piece| kind_char(piece.kind)) == promotion
+        })
+    }
+    // Finds the legal move matching the given standard algebraic notation. Check and annotation
+    // suffixes are ignored, and "0-0" and promotions without "=" such as "e8Q" are accepted too, as
+    // are pieces named by more of their origin square than needed, such as "Ngf3" or "Ng1f3", as
+    // long as only one move fits.
+    pub fn parse_san(&self, text: &str) -> Option<Move> {
+        let mut text = text.trim().trim_end_matches(['+', '#', '!', '?']).replace('0', "O");
+        let mut chars = text.chars().rev();
+        if let (Some(last), Some(before)) = (chars.next(), chars.next()) {
+            if "QRBN".contains(last) && before.is_ascii_digit() {
+                text.insert(text.len() - 1, '=');
+            }
+        }
+        let moves = self.legal_moves();
+        if let Some(mv) = moves.iter().find(|mv| mv.san_without_suffix(self) == text) {
+            return Some(*mv);
+        }
+        let mut matches = moves.into_iter().filter(|mv| {
+            if mv.piece.kind == Kind::Pawn || mv.castle.is_some() {
+                return false;
+            }
+            let from = square_name(mv.from);
+            let capture = if mv.attack.is_some() { "x" } else { "" };
+            [&from[0..1], &from[1..2], &from[..]].iter()
+                .any(|origin| text == format!("{}{}{}{}", piece_letter(mv.piece.kind), origin, capture, square_name(mv.to)))
+        });
+        match (matches.next(), matches.next()) {
+            (Some(mv), None) => Some(mv),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Castling both ways, promotions with and without captures, en passant, and three knights
+    // reaching d4, which need a file, a rank and a whole square to tell apart
+    const POSITION: &str = "r1n1k2r/1P6/8/1N1pP3/8/1N3N2/8/R3K2R w KQkq d6 0 1";
+
+    #[test]
+    fn notation_round_trips() {
+        let board = Board::from_fen(POSITION).unwrap();
+        for mv in board.legal_moves() {
+            assert_eq!(board.parse_san(&mv.to_san(&board)), Some(mv), "{}", mv.to_san(&board));
+            assert_eq!(board.parse_uci_move(&mv.to_uci()), Some(mv), "{}", mv.to_uci());
+        }
+    }
+
+    #[test]
+    fn writes_san_and_uci() {
+        let board = Board::from_fen(POSITION).unwrap();
+        for (uci, san) in [
+            ("e1g1", "O-O"),
+            ("e1c1", "O-O-O"),
+            ("b7b8q", "b8=Q"),
+            ("b7c8q", "bxc8=Q+"),
+            ("b7a8n", "bxa8=N"),
+            ("e5d6", "exd6"),
+            ("b5d4", "N5d4"),
+            ("b3d4", "Nb3d4"),
+            ("f3d4", "Nfd4"),
+            ("h1h8", "Rxh8+"),
+        ] {
+            let mv = board.parse_uci_move(uci).unwrap();
+            assert_eq!(mv.to_san(&board), san);
+            assert_eq!(mv.to_uci(), uci);
+        }
+        let mate = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(mate.parse_uci_move("a1a8").unwrap().to_san(&mate), "Ra8#");
+    }
+
+    #[test]
+    fn accepts_variant_san() {
+        let board = Board::from_fen(POSITION).unwrap();
+        assert_eq!(board.parse_san("0-0-0"), board.parse_uci_move("e1c1"));
+        assert_eq!(board.parse_san("b8Q+"), board.parse_uci_move("b7b8q"));
+        assert_eq!(board.parse_san("exd6!?"), board.parse_uci_move("e5d6"));
+        let start = Board::new();
+        for text in ["Nf3", "Ngf3", "N1f3", "Ng1f3"] {
+            assert_eq!(start.parse_san(text), start.parse_uci_move("g1f3"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        let board = Board::from_fen(POSITION).unwrap();
+        // ambiguous, or disambiguated by a file or rank the rivals share
+        for text in ["Nd4", "Nbd4", "N3d4", "", "d6", "Ke3", "Qh5", "b8", "b8=K", "O-O-O-O", "Nhf3", "xd6"] {
+            assert_eq!(board.parse_san(text), None, "{}", text);
+        }
+        for text in ["", "e1", "e1g1x", "e9e8", "i2i4", "b7b8", "b7b8k", "b7b8x", "e2e4", "e5e6q"] {
+            assert_eq!(board.parse_uci_move(text), None, "{}", text);
+        }
+        assert_eq!(Board::new().parse_san("Nhf3"), None);
+    }
+}
@@ -0,0 +1,161 @@
+use super::*;
+
+// Random keys for Zobrist hashing. A position's hash is the xor of the keys of everything in it,
+// so moves can update it by xoring out what changed and xoring in what replaced it.
+
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    // white queenside, white kingside, black queenside, black kingside
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+}
+
+// splitmix64, which is simple enough to run at compile time
+const fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+const fn generate() -> Keys {
+    let mut state = 0x636f72726f646564;
+    let mut keys = Keys { pieces: [[[0; 64]; 6]; 2], black_to_move: 0, castling: [0; 4], en_passant: [0; 8] };
+    let mut color = 0;
+    while color < 2 {
+        let mut kind = 0;
+        while kind < 6 {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[color][kind][square] = next(&mut state);
+                square += 1;
+            }
+            kind += 1;
+        }
+        color += 1;
+    }
+    keys.black_to_move = next(&mut state);
+    let mut i = 0;
+    while i < 4 {
+        keys.castling[i] = next(&mut state);
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 8 {
+        keys.en_passant[i] = next(&mut state);
+        i += 1;
+    }
+    keys
+}
+
+const KEYS: Keys = generate();
+
+pub fn piece_key(piece: Piece, loc: Loc) -> u64 {
+    KEYS.pieces[piece.color as usize][piece.kind as usize][(loc.rank() * 8 + loc.file()) as usize]
+}
+pub fn turn_key(turn: Color) -> u64 {
+    match turn {
+        Color::White => 0,
+        Color::Black => KEYS.black_to_move,
+    }
+}
+pub fn castling_key(castling: &CastleState) -> u64 {
+    let lost = [castling.white.0, castling.white.1, castling.black.0, castling.black.1];
+    (0..4).filter(|i| !lost[*i]).fold(0, |key, i| key ^ KEYS.castling[i])
+}
+pub fn en_passant_key(file: Option<isize>) -> u64 {
+    file.map_or(0, |file| KEYS.en_passant[file as usize])
+}
+
+impl Board {
+    // Computes the hash from scratch, rather than incrementally like apply_move does
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = turn_key(self.turn) ^ castling_key(&self.castling) ^ en_passant_key(self.en_passant_file);
+        for x in 0..8 {
+            for y in 0..8 {
+                if let Some(piece) = self[Loc(x, y)] {
+                    hash ^= piece_key(piece, Loc(x, y));
+                }
+            }
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays the moves from the position, checking the incremental hash against one computed from
+    // scratch after each, then takes them all back and checks the position is restored
+    fn check_moves(fen: &str, moves: &[&str]) {
+        let mut board = Board::from_fen(fen).unwrap();
+        let before = board.clone();
+        let mut played = vec![];
+        for text in moves {
+            let mv = board.parse_uci_move(text).unwrap();
+            played.push((mv, board.make_move(mv)));
+            assert_eq!(board.hash, board.compute_hash(), "after {} from {}", text, fen);
+        }
+        for (mv, undo) in played.into_iter().rev() {
+            board.unmake_move(mv, undo);
+            assert_eq!(board.hash, board.compute_hash(), "taking back {} from {}", mv.to_uci(), fen);
+        }
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn castling() {
+        check_moves("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &["e1g1", "e8c8"]);
+        // moving a rook loses one right, moving the king both
+        check_moves("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &["h1h2", "e8d8"]);
+    }
+
+    #[test]
+    fn en_passant() {
+        check_moves(Board::STARTING_FEN, &["e2e4", "g8f6", "e4e5", "d7d5", "e5d6"]);
+    }
+
+    #[test]
+    fn promotion() {
+        check_moves("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", &["b7b8q"]);
+        // capturing the rook on a8 takes away black's right to castle queenside
+        check_moves("r3k3/1P6/8/8/8/8/8/4K3 w q - 0 1", &["b7a8n"]);
+    }
+
+    #[test]
+    fn capturing_a_rook_removes_castling_rights() {
+        check_moves("r3k3/8/8/8/8/8/8/R3K3 w Qq - 0 1", &["a1a8"]);
+        let mut board = Board::from_fen("r3k3/8/8/8/8/8/8/R3K3 w Qq - 0 1").unwrap();
+        board.apply_move(board.parse_uci_move("a1a8").unwrap());
+        assert_eq!(board.to_fen(), "R3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn null_move() {
+        // the en passant file goes away with the null move and comes back when it is taken back
+        let mut board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let before = board.clone();
+        let undo = board.make_null_move();
+        assert_eq!(board.hash, board.compute_hash());
+        assert_ne!(board.hash, before.hash);
+        board.unmake_null_move(undo);
+        assert_eq!(board.hash, board.compute_hash());
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn transpositions_hash_alike() {
+        let mut one = Board::new();
+        let mut other = Board::new();
+        for text in ["g1f3", "g8f6", "b1c3"] {
+            one.apply_move(one.parse_uci_move(text).unwrap());
+        }
+        for text in ["b1c3", "g8f6", "g1f3"] {
+            other.apply_move(other.parse_uci_move(text).unwrap());
+        }
+        assert_eq!(one.hash, other.hash);
+    }
+}
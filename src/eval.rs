@@ -0,0 +1,166 @@
+use crate::board::*;
+
+mod positional;
+mod pst;
+pub use positional::{Positional, Term, Weights, Breakdown, PARAMETERS};
+pub use pst::PieceSquare;
+
+pub type Evaluation = i32;
+pub fn flip(color: Color) -> Evaluation {
+    match color {
+        Color::White => 1,
+        Color::Black => -1
+    }
+}
+
+// Score for a checkmate, well above anything material can add up to
+pub const WIN: Evaluation = 1_000_000;
+
+// How much material is left, from MAX_PHASE with all of it on the board down to 0 with only kings
+// and pawns, counting a minor piece as 1, a rook as 2 and a queen as 4
+pub const MAX_PHASE: Evaluation = 24;
+pub fn game_phase(board: &Board) -> Evaluation {
+    use Kind::*;
+    let count = |kind: Kind| board.kind_bitboard(kind).count_ones() as Evaluation;
+    let phase = count(Knight) + count(Bishop) + 2 * count(Rook) + 4 * count(Queen);
+    // promotions can leave more than the starting material on the board
+    phase.min(MAX_PHASE)
+}
+// Blends a middlegame and an endgame score according to the game phase
+pub fn taper(middlegame: Evaluation, endgame: Evaluation, phase: Evaluation) -> Evaluation {
+    (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+// Scores positions for the search strategies, from White's perspective, so any search can be
+// paired with any way of judging the positions it reaches. Evaluators are Send, like strategies.
+pub trait Evaluator: Send {
+    // Scores a position where the game is still being played
+    fn evaluate(&self, board: &Board) -> Evaluation;
+    // Like evaluate, but scores finished games by their outcome
+    fn evaluate_state(&self, board: &Board, state: &WinState) -> Evaluation {
+        match state {
+            WinState::Playing => self.evaluate(board),
+            WinState::Winner(color) => flip(*color) * WIN,
+            WinState::Draw => 0,
+        }
+    }
+}
+
+// A pair of evaluators scores a position with the sum of their scores
+impl<A: Evaluator, B: Evaluator> Evaluator for (A, B) {
+    fn evaluate(&self, board: &Board) -> Evaluation {
+        self.0.evaluate(board) + self.1.evaluate(board)
+    }
+}
+
+// Counts material in pawns, plus a little for each rank a piece has advanced
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Material;
+
+impl Material {
+    pub fn evaluate_piece(piece: Piece) -> Evaluation {
+        use Kind::*;
+        flip(piece.color) * match piece.kind {
+            Pawn => 1,
+            Knight => 3,
+            Bishop => 3,
+            Rook => 5,
+            Queen => 9,
+            King => 4
+        }
+    }
+}
+impl Evaluator for Material {
+    fn evaluate(&self, board: &Board) -> Evaluation {
+        let mut score = 0;
+        for loc in Squares(board.occupied()) {
+            let piece = board[loc].unwrap();
+            score += Material::evaluate_piece(piece) * 100 + flip(piece.color) * (loc.rank() - piece.color.back_rank()).abs() as Evaluation;
+        }
+        score
+    }
+}
+
+// Rewards pieces for the number of squares they attack that aren't taken by their own side
+#[derive(Debug, Clone, Copy)]
+pub struct Mobility {
+    // Score per square
+    pub weight: Evaluation,
+}
+
+impl Mobility {
+    // Pawns and kings are left out, since having squares to go to matters little for them
+    pub fn attacked_squares(board: &Board, color: Color) -> Evaluation {
+        let own = board.color_bitboard(color);
+        let pieces = own & !board.kind_bitboard(Kind::Pawn) & !board.kind_bitboard(Kind::King);
+        Squares(pieces).map(|loc| (board.attacks_from(loc) & !own).count_ones() as Evaluation).sum()
+    }
+}
+impl Evaluator for Mobility {
+    fn evaluate(&self, board: &Board) -> Evaluation {
+        self.weight * (Mobility::attacked_squares(board, Color::White) - Mobility::attacked_squares(board, Color::Black))
+    }
+}
+
+// Scores each position with a pseudo-random number between -amplitude and amplitude. It depends
+// only on the seed and the position, so a search sees the same score every time it gets there.
+#[derive(Debug, Clone, Copy)]
+pub struct Noise {
+    pub seed: u64,
+    pub amplitude: Evaluation,
+}
+
+impl Evaluator for Noise {
+    fn evaluate(&self, board: &Board) -> Evaluation {
+        // the finalizer of splitmix64, to spread the hash bits over the whole number
+        let mut z = board.hash ^ self.seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        (z % (2 * self.amplitude as u64 + 1)) as Evaluation - self.amplitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The same position with the colours swapped and the board flipped, and White to move
+    const POSITION: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const MIRRORED: &str = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+
+    #[test]
+    fn material_is_colour_symmetric() {
+        assert_eq!(Material.evaluate(&Board::new()), 0);
+        let position = Board::from_fen(POSITION).unwrap();
+        let mirrored = Board::from_fen(MIRRORED).unwrap();
+        assert_eq!(Material.evaluate(&position), -Material.evaluate(&mirrored));
+        assert_ne!(Material.evaluate(&position), 0);
+    }
+
+    #[test]
+    fn pairs_add_up() {
+        let mobility = Mobility { weight: 3 };
+        let noise = Noise { seed: 7, amplitude: 20 };
+        for fen in [Board::STARTING_FEN, POSITION, MIRRORED] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!((Material, mobility).evaluate(&board), Material.evaluate(&board) + mobility.evaluate(&board));
+            assert_eq!((Material, (mobility, noise)).evaluate(&board), Material.evaluate(&board) + mobility.evaluate(&board) + noise.evaluate(&board));
+        }
+    }
+
+    #[test]
+    fn noise_depends_only_on_the_seed_and_position() {
+        let noise = Noise { seed: 42, amplitude: 50 };
+        let scores = |noise: Noise| [Board::STARTING_FEN, POSITION, MIRRORED].map(|fen| noise.evaluate(&Board::from_fen(fen).unwrap()));
+        assert_eq!(scores(noise), scores(Noise { seed: 42, amplitude: 50 }));
+        assert!(scores(noise).iter().all(|score| score.abs() <= 50));
+        assert_ne!(scores(noise), scores(Noise { seed: 43, amplitude: 50 }));
+        // reaching the same position another way gives the same score
+        let mut board = Board::new();
+        for text in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            board.apply_move(board.parse_uci_move(text).unwrap());
+        }
+        assert_eq!(noise.evaluate(&board), noise.evaluate(&Board::new()));
+    }
+}
@@ -0,0 +1,441 @@
+use crate::board::*;
+use super::*;
+
+const FILE_A: Bitboard = 0x0101010101010101;
+
+fn file_mask(file: isize) -> Bitboard {
+    if (0..8).contains(&file) { FILE_A << file } else { 0 }
+}
+fn adjacent_files(file: isize) -> Bitboard {
+    file_mask(file - 1) | file_mask(file + 1)
+}
+// The ranks in front of the given rank, from the point of view of the given color
+fn ranks_ahead(rank: isize, color: Color) -> Bitboard {
+    match color {
+        Color::White if (0..7).contains(&rank) => !0 << ((rank + 1) * 8),
+        Color::Black if (1..8).contains(&rank) => !0 >> ((8 - rank) * 8),
+        _ => 0,
+    }
+}
+
+// The things besides piece placement that the positional evaluation looks at. Each is counted
+// for both sides and multiplied by a weight.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Term {
+    // Pawns on a file beyond the first
+    DoubledPawn,
+    // Pawns with no friendly pawns on the files next to them
+    IsolatedPawn,
+    // Ranks advanced by pawns that no enemy pawn can stop, so further advanced ones count for more
+    PassedPawn,
+    // Squares attacked by knights, bishops, rooks and queens
+    Mobility,
+    // Having two or more bishops
+    BishopPair,
+    // Rooks on a file without pawns
+    RookOpenFile,
+    // Rooks on a file with only enemy pawns
+    RookHalfOpenFile,
+    // Pawns on the two ranks in front of the king, on its file or the ones next to it
+    PawnShield,
+    // Enemy pieces attacking the king or the squares around it
+    KingAttacker,
+}
+pub const TERMS: usize = 9;
+
+impl Term {
+    pub const ALL: [Term; TERMS] = [
+        Term::DoubledPawn,
+        Term::IsolatedPawn,
+        Term::PassedPawn,
+        Term::Mobility,
+        Term::BishopPair,
+        Term::RookOpenFile,
+        Term::RookHalfOpenFile,
+        Term::PawnShield,
+        Term::KingAttacker,
+    ];
+    pub fn name(self) -> &'static str {
+        use Term::*;
+        match self {
+            DoubledPawn => "doubled pawns",
+            IsolatedPawn => "isolated pawns",
+            PassedPawn => "passed pawns",
+            Mobility => "mobility",
+            BishopPair => "bishop pair",
+            RookOpenFile => "rooks on open files",
+            RookHalfOpenFile => "rooks on half-open files",
+            PawnShield => "pawn shield",
+            KingAttacker => "king attackers",
+        }
+    }
+}
+
+// How much each occurrence of a term is worth, indexed by Term, in the middlegame and the endgame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Weights {
+    pub middlegame: [Evaluation; TERMS],
+    pub endgame: [Evaluation; TERMS],
+}
+impl Default for Weights {
+    fn default() -> Weights {
+        // in the order of Term::ALL
+        Weights {
+            middlegame: [-10, -10, 5, 4, 30, 25, 10, 10, -15],
+            endgame: [-20, -15, 15, 4, 50, 10, 5, 0, 0],
+        }
+    }
+}
+
+// How many times each term applies to one side, indexed by Term
+pub fn count_terms(board: &Board, color: Color) -> [Evaluation; TERMS] {
+    use Kind::*;
+    let mut counts = [0; TERMS];
+    let enemy = color.opposite();
+    let pawns = board.piece_bitboard(Piece { kind: Pawn, color });
+    let enemy_pawns = board.piece_bitboard(Piece { kind: Pawn, color: enemy });
+    for file in 0..8 {
+        let on_file = (pawns & file_mask(file)).count_ones() as Evaluation;
+        counts[Term::DoubledPawn as usize] += (on_file - 1).max(0);
+    }
+    for loc in Squares(pawns) {
+        if pawns & adjacent_files(loc.file()) == 0 {
+            counts[Term::IsolatedPawn as usize] += 1;
+        }
+        let blockers = (file_mask(loc.file()) | adjacent_files(loc.file())) & ranks_ahead(loc.rank(), color);
+        if enemy_pawns & blockers == 0 {
+            counts[Term::PassedPawn as usize] += (loc.rank() - color.pawn_rank()).abs() as Evaluation;
+        }
+    }
+    counts[Term::Mobility as usize] = Mobility::attacked_squares(board, color);
+    if board.piece_bitboard(Piece { kind: Bishop, color }).count_ones() >= 2 {
+        counts[Term::BishopPair as usize] = 1;
+    }
+    for loc in Squares(board.piece_bitboard(Piece { kind: Rook, color })) {
+        let file = file_mask(loc.file());
+        if (pawns | enemy_pawns) & file == 0 {
+            counts[Term::RookOpenFile as usize] += 1;
+        }
+        else if pawns & file == 0 {
+            counts[Term::RookHalfOpenFile as usize] += 1;
+        }
+    }
+    if let Some(king) = board.king_loc(color) {
+        let near_files = file_mask(king.file()) | adjacent_files(king.file());
+        let next_ranks = ranks_ahead(king.rank(), color) & !ranks_ahead(king.rank() + 2 * color.forward(), color);
+        counts[Term::PawnShield as usize] = (pawns & near_files & next_ranks).count_ones() as Evaluation;
+        let zone = KING_ATTACKS[square(king)] | bit(king);
+        let attackers = board.color_bitboard(enemy) & !board.kind_bitboard(Pawn) & !board.kind_bitboard(King);
+        counts[Term::KingAttacker as usize] = Squares(attackers).filter(|loc| board.attacks_from(*loc) & zone != 0).count() as Evaluation;
+    }
+    counts
+}
+
+// Piece-square tables plus pawn structure, mobility and king safety, all tapered between the
+// middlegame and the endgame
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Positional {
+    pub pieces: PieceSquare,
+    pub weights: Weights,
+}
+
+impl Positional {
+    // The middlegame and endgame scores of one side, from its own perspective, split into the piece
+    // placement and each term
+    fn side_scores(&self, board: &Board, color: Color) -> [(Evaluation, Evaluation); TERMS + 1] {
+        let (mut middlegame, mut endgame) = (0, 0);
+        for loc in Squares(board.color_bitboard(color)) {
+            let (mg, eg) = self.pieces.piece_scores(board[loc].unwrap(), loc);
+            middlegame += mg;
+            endgame += eg;
+        }
+        let counts = count_terms(board, color);
+        let mut scores = [(middlegame, endgame); TERMS + 1];
+        for term in 0..TERMS {
+            scores[term + 1] = (self.weights.middlegame[term] * counts[term], self.weights.endgame[term] * counts[term]);
+        }
+        scores
+    }
+    // Explains the evaluation of a position
+    pub fn breakdown(&self, board: &Board) -> Breakdown {
+        let phase = game_phase(board);
+        let white = self.side_scores(board, Color::White);
+        let black = self.side_scores(board, Color::Black);
+        let names = std::iter::once("piece placement").chain(Term::ALL.iter().map(|term| term.name()));
+        Breakdown {
+            phase,
+            parts: names.zip(white.iter().zip(black.iter())).map(|(name, (white, black))| {
+                (name, taper(white.0, white.1, phase), taper(black.0, black.1, phase))
+            }).collect(),
+            total: self.evaluate(board),
+        }
+    }
+}
+impl Evaluator for Positional {
+    fn evaluate(&self, board: &Board) -> Evaluation {
+        let (mut middlegame, mut endgame) = (0, 0);
+        for color in [Color::White, Color::Black] {
+            for (mg, eg) in self.side_scores(board, color) {
+                middlegame += flip(color) * mg;
+                endgame += flip(color) * eg;
+            }
+        }
+        taper(middlegame, endgame, game_phase(board))
+    }
+}
+
+// The numbers the evaluation is made of, laid out as one list for tuning: the piece values, the
+// tables and the term weights, each middlegame first and then endgame. The weights file has the
+// same groups, each name followed by its numbers.
+const GROUPS: [(&str, usize); 6] = [
+    ("middlegame_values", 6),
+    ("endgame_values", 6),
+    ("middlegame_tables", 6 * 64),
+    ("endgame_tables", 6 * 64),
+    ("middlegame_weights", TERMS),
+    ("endgame_weights", TERMS),
+];
+pub const PARAMETERS: usize = 2 * 6 + 2 * 6 * 64 + 2 * TERMS;
+
+impl Positional {
+    pub fn parameters(&self) -> Vec<Evaluation> {
+        let mut parameters = vec![];
+        parameters.extend(self.pieces.middlegame_values);
+        parameters.extend(self.pieces.endgame_values);
+        parameters.extend(self.pieces.middlegame_tables.iter().flatten());
+        parameters.extend(self.pieces.endgame_tables.iter().flatten());
+        parameters.extend(self.weights.middlegame);
+        parameters.extend(self.weights.endgame);
+        parameters
+    }
+    pub fn set_parameters(&mut self, parameters: &[Evaluation]) {
+        assert_eq!(parameters.len(), PARAMETERS);
+        let (values, rest) = parameters.split_at(12);
+        let (tables, weights) = rest.split_at(2 * 6 * 64);
+        self.pieces.middlegame_values.copy_from_slice(&values[..6]);
+        self.pieces.endgame_values.copy_from_slice(&values[6..]);
+        for (kind, table) in tables.chunks(64).enumerate() {
+            if kind < 6 {
+                self.pieces.middlegame_tables[kind].copy_from_slice(table);
+            }
+            else {
+                self.pieces.endgame_tables[kind - 6].copy_from_slice(table);
+            }
+        }
+        self.weights.middlegame.copy_from_slice(&weights[..TERMS]);
+        self.weights.endgame.copy_from_slice(&weights[TERMS..]);
+    }
+    // What each parameter is multiplied by in the evaluation of the position, which is the sum of
+    // those products up to rounding. Parameters that don't matter are left out.
+    pub fn coefficients(&self, board: &Board) -> Vec<(usize, f64)> {
+        let phase = game_phase(board);
+        let middlegame = phase as f64 / MAX_PHASE as f64;
+        let endgame = 1.0 - middlegame;
+        let mut coefficients = vec![];
+        for loc in Squares(board.occupied()) {
+            let piece = board[loc].unwrap();
+            let (kind, index, sign) = (piece.kind as usize, PieceSquare::table_index(piece, loc), flip(piece.color) as f64);
+            coefficients.push((kind, sign * middlegame));
+            coefficients.push((6 + kind, sign * endgame));
+            coefficients.push((12 + kind * 64 + index, sign * middlegame));
+            coefficients.push((12 + 6 * 64 + kind * 64 + index, sign * endgame));
+        }
+        let white = count_terms(board, Color::White);
+        let black = count_terms(board, Color::Black);
+        for term in 0..TERMS {
+            let count = (white[term] - black[term]) as f64;
+            coefficients.push((12 + 2 * 6 * 64 + term, count * middlegame));
+            coefficients.push((12 + 2 * 6 * 64 + TERMS + term, count * endgame));
+        }
+        // add up the coefficients of each parameter, dropping those that cancel out
+        coefficients.sort_by_key(|(parameter, _)| *parameter);
+        let mut merged: Vec<(usize, f64)> = vec![];
+        for (parameter, coefficient) in coefficients {
+            match merged.last_mut() {
+                Some((last, sum)) if *last == parameter => *sum += coefficient,
+                _ => merged.push((parameter, coefficient)),
+            }
+        }
+        merged.retain(|(_, coefficient)| coefficient.abs() > 1e-9);
+        merged
+    }
+    // The parameters in the format of a weights file
+    pub fn to_text(&self) -> String {
+        let parameters = self.parameters();
+        let mut text = String::new();
+        let mut start = 0;
+        for (name, len) in GROUPS {
+            text.push_str(name);
+            // tables go one rank per line, so they look like the board
+            let per_line = if len > 64 { 8 } else { len };
+            for line in parameters[start..start + len].chunks(per_line) {
+                text.push('\n');
+                text.push_str(&line.iter().map(|value| format!("{:>5}", value)).collect::<String>());
+            }
+            text.push('\n');
+            start += len;
+        }
+        text
+    }
+    // Reads a weights file. Groups that are missing keep their default values.
+    pub fn from_text(text: &str) -> Result<Positional, String> {
+        let mut parameters = Positional::default().parameters();
+        let mut tokens = text.split_whitespace();
+        while let Some(name) = tokens.next() {
+            let Some(group) = GROUPS.iter().position(|(group, _)| *group == name) else {
+                return Err(format!("Unknown group: {}", name));
+            };
+            let start = GROUPS[..group].iter().map(|(_, len)| len).sum::<usize>();
+            for parameter in &mut parameters[start..start + GROUPS[group].1] {
+                let token = tokens.next().ok_or(format!("Too few numbers for {}", name))?;
+                *parameter = token.parse().map_err(|_| format!("Bad number for {}: {}", name, token))?;
+            }
+        }
+        let mut positional = Positional::default();
+        positional.set_parameters(&parameters);
+        Ok(positional)
+    }
+    pub fn load(path: &str) -> Result<Positional, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("Can't read {}: {}", path, err))?;
+        Positional::from_text(&text)
+    }
+}
+
+// What a positional evaluation is made up of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakdown {
+    pub phase: Evaluation,
+    // The name of each part, with its tapered score for White and for Black from their own
+    // perspectives
+    pub parts: Vec<(&'static str, Evaluation, Evaluation)>,
+    // The evaluation from White's perspective. It can differ slightly from adding up the parts,
+    // since those are tapered separately.
+    pub total: Evaluation,
+}
+impl std::fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{:<26}{:>8}{:>8}{:>8}", "", "White", "Black", "Total")?;
+        for (name, white, black) in &self.parts {
+            writeln!(f, "{:<26}{:>8}{:>8}{:>8}", name, white, black, white - black)?;
+        }
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        write!(f, "Evaluation: {}", self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pawn_structure() {
+        // White: doubled and isolated c pawns, a passed pawn on e5 and a shielded king on g1.
+        // Black: two blocked, isolated pawns and a king with no shield.
+        let board = Board::from_fen("6k1/8/8/4P3/1p6/2P2p2/2P2PPP/6K1 w - - 0 1").unwrap();
+        let white = count_terms(&board, Color::White);
+        let black = count_terms(&board, Color::Black);
+        assert_eq!(white[Term::DoubledPawn as usize], 1);
+        assert_eq!(white[Term::IsolatedPawn as usize], 2);
+        assert_eq!(white[Term::PassedPawn as usize], 3);
+        assert_eq!(white[Term::PawnShield as usize], 3);
+        assert_eq!(black[Term::DoubledPawn as usize], 0);
+        assert_eq!(black[Term::IsolatedPawn as usize], 2);
+        assert_eq!(black[Term::PassedPawn as usize], 0);
+        assert_eq!(black[Term::PawnShield as usize], 0);
+    }
+
+    #[test]
+    fn pieces_and_kings() {
+        let board = Board::from_fen("2r3k1/5ppp/8/8/8/8/PP3n2/2BBKR2 w - - 0 1").unwrap();
+        let white = count_terms(&board, Color::White);
+        let black = count_terms(&board, Color::Black);
+        assert_eq!(white[Term::BishopPair as usize], 1);
+        assert_eq!(white[Term::RookHalfOpenFile as usize], 1);
+        assert_eq!(white[Term::KingAttacker as usize], 1);
+        assert_eq!(black[Term::RookOpenFile as usize], 1);
+        assert_eq!(black[Term::KingAttacker as usize], 0);
+    }
+
+    // Checks how many times the term applies to each side, and that the breakdown scores it as
+    // that many times its tapered weight
+    fn check_term(fen: &str, term: Term, white: Evaluation, black: Evaluation) {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(count_terms(&board, Color::White)[term as usize], white, "White's {} in {}", term.name(), fen);
+        assert_eq!(count_terms(&board, Color::Black)[term as usize], black, "Black's {} in {}", term.name(), fen);
+        let evaluator = Positional::default();
+        let breakdown = evaluator.breakdown(&board);
+        let weight = |count: Evaluation| taper(evaluator.weights.middlegame[term as usize] * count, evaluator.weights.endgame[term as usize] * count, breakdown.phase);
+        assert!(breakdown.parts.contains(&(term.name(), weight(white), weight(black))), "{:?}", breakdown.parts);
+        assert_eq!(breakdown.total, evaluator.evaluate(&board));
+    }
+
+    #[test]
+    fn doubled_pawns() {
+        // a tripled pawn counts twice
+        check_term("4k3/6p1/6p1/8/2P5/2P5/2P5/4K3 w - - 0 1", Term::DoubledPawn, 2, 1);
+    }
+
+    #[test]
+    fn isolated_pawns() {
+        check_term("4k3/pp5p/8/8/8/8/P1P4P/4K3 w - - 0 1", Term::IsolatedPawn, 3, 1);
+    }
+
+    #[test]
+    fn passed_pawns() {
+        // b5 can still be stopped by c7, and h5 counts the three ranks it has advanced
+        check_term("4k3/2p5/8/1P5P/8/8/8/4K3 w - - 0 1", Term::PassedPawn, 3, 0);
+    }
+
+    #[test]
+    fn mobility() {
+        // a knight in the centre attacks eight squares and one in the corner two
+        check_term("4k3/8/8/8/3N4/8/8/N3K3 w - - 0 1", Term::Mobility, 10, 0);
+    }
+
+    #[test]
+    fn bishop_pair() {
+        check_term("2b1kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", Term::BishopPair, 0, 1);
+    }
+
+    #[test]
+    fn rooks_on_open_and_half_open_files() {
+        // the h file is open, the a file is half-open for White and closed for Black
+        let fen = "r3k3/p7/8/8/8/8/1P6/R3K2R w - - 0 1";
+        check_term(fen, Term::RookOpenFile, 1, 0);
+        check_term(fen, Term::RookHalfOpenFile, 1, 0);
+    }
+
+    #[test]
+    fn pawn_shield() {
+        // g3 is still in front of the king, but g4 is too far away
+        check_term("6k1/7p/8/8/6P1/6P1/5P2/6K1 w - - 0 1", Term::PawnShield, 2, 1);
+    }
+
+    #[test]
+    fn king_attackers() {
+        // the knight and rook both reach squares next to White's king, while the distant queen
+        // reaches none next to Black's
+        check_term("4k3/8/8/8/8/8/3n3r/Q3K3 w - - 0 1", Term::KingAttacker, 2, 0);
+    }
+
+    #[test]
+    fn coefficients_add_up_to_the_evaluation() {
+        let evaluator = Positional::default();
+        let parameters = evaluator.parameters();
+        for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"] {
+            let board = Board::from_fen(fen).unwrap();
+            let sum: f64 = evaluator.coefficients(&board).iter().map(|(parameter, coefficient)| parameters[*parameter] as f64 * coefficient).sum();
+            assert!((sum - evaluator.evaluate(&board) as f64).abs() <= 1.0, "{}: {} vs {}", fen, sum, evaluator.evaluate(&board));
+        }
+    }
+
+    #[test]
+    fn weights_file_round_trips() {
+        let mut evaluator = Positional::default();
+        evaluator.weights.endgame[Term::PassedPawn as usize] = 42;
+        evaluator.pieces.middlegame_tables[Kind::Knight as usize][27] = -7;
+        assert_eq!(Positional::from_text(&evaluator.to_text()), Ok(evaluator));
+        assert!(Positional::from_text("middlegame_weights 1 2 3").is_err());
+        assert!(Positional::from_text("bishop_weights 1").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::board::*;
+use super::*;
+
+// The tables are written as a board is drawn, from a8 to h1, as seen from White's side
+const PAWN: [Evaluation; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+// In the endgame what matters most about a pawn is how close it is to promoting
+const PAWN_ENDGAME: [Evaluation; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    20,  20,  20,  20,  20,  20,  20,  20,
+    10,  10,  10,  10,  10,  10,  10,  10,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+const ROOK: [Evaluation; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+const KNIGHT: [Evaluation; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+const BISHOP: [Evaluation; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+const QUEEN: [Evaluation; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+// While there is still material around, the king should hide behind its pawns
+const KING: [Evaluation; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+// but once it's gone, the king is a strong piece that belongs in the center
+const KING_ENDGAME: [Evaluation; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+// Scores each piece by its value plus a bonus for the square it stands on, with separate numbers
+// for the middlegame and the endgame that are blended by the game phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceSquare {
+    // Indexed by kind
+    pub middlegame_values: [Evaluation; 6],
+    pub endgame_values: [Evaluation; 6],
+    // Indexed by kind, then by square from a8 to h1 as seen from White's side
+    pub middlegame_tables: [[Evaluation; 64]; 6],
+    pub endgame_tables: [[Evaluation; 64]; 6],
+}
+
+impl PieceSquare {
+    // Where a piece on the square is found in the tables
+    pub fn table_index(piece: Piece, loc: Loc) -> usize {
+        let rank = match piece.color {
+            Color::White => 7 - loc.rank(),
+            Color::Black => loc.rank(),
+        };
+        (rank * 8 + loc.file()) as usize
+    }
+    // The middlegame and endgame scores of a single piece, from its own side's perspective
+    pub fn piece_scores(&self, piece: Piece, loc: Loc) -> (Evaluation, Evaluation) {
+        let (kind, index) = (piece.kind as usize, PieceSquare::table_index(piece, loc));
+        (self.middlegame_values[kind] + self.middlegame_tables[kind][index], self.endgame_values[kind] + self.endgame_tables[kind][index])
+    }
+}
+impl Default for PieceSquare {
+    fn default() -> PieceSquare {
+        // in the order of Kind: pawn, rook, knight, bishop, queen, king. The king can't be traded, so
+        // it needs no value.
+        PieceSquare {
+            middlegame_values: [100, 500, 320, 330, 900, 0],
+            endgame_values: [120, 520, 300, 320, 900, 0],
+            middlegame_tables: [PAWN, ROOK, KNIGHT, BISHOP, QUEEN, KING],
+            endgame_tables: [PAWN_ENDGAME, ROOK, KNIGHT, BISHOP, QUEEN, KING_ENDGAME],
+        }
+    }
+}
+impl Evaluator for PieceSquare {
+    fn evaluate(&self, board: &Board) -> Evaluation {
+        let (mut middlegame, mut endgame) = (0, 0);
+        for loc in Squares(board.occupied()) {
+            let piece = board[loc].unwrap();
+            let (mg, eg) = self.piece_scores(piece, loc);
+            middlegame += flip(piece.color) * mg;
+            endgame += flip(piece.color) * eg;
+        }
+        taper(middlegame, endgame, game_phase(board))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetric_positions_score_zero() {
+        let evaluator = PieceSquare::default();
+        assert_eq!(evaluator.evaluate(&Board::new()), 0);
+        let white = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
+        let black = Board::from_fen("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1").unwrap();
+        assert_eq!(evaluator.evaluate(&white), -evaluator.evaluate(&black));
+    }
+
+    #[test]
+    fn kings_centralize_in_the_endgame() {
+        let evaluator = PieceSquare::default();
+        let cornered = Board::from_fen("7k/8/8/8/3K4/8/8/8 w - - 0 1").unwrap();
+        assert!(evaluator.evaluate(&cornered) > 0);
+        let sheltered = Board::from_fen("rnbq1rk1/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1RK1 w - - 0 1").unwrap();
+        let exposed = Board::from_fen("rnbq1rk1/pppppppp/8/8/3K4/8/PPPPPPPP/RNBQ1R2 w - - 0 1").unwrap();
+        assert!(evaluator.evaluate(&sheltered) > evaluator.evaluate(&exposed));
+    }
+}
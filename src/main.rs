@@ -1,11 +1,23 @@
 pub mod board;
+pub mod eval;
 pub mod strategy;
+pub mod uci;
+pub mod pgn;
+pub mod perft;
+pub mod tune;
 
+use std::io::Write;
+use std::time::{Duration, Instant};
 use board::*;
-use strategy::Strategy;
+use eval::{Material, Mobility, Noise, PieceSquare, Positional};
+use pgn::Game;
+use strategy::{Reporter, SearchLimits, Strategy, TimeControl};
 use strategy::user;
 use strategy::random;
 use strategy::minimax;
+use strategy::alphabeta;
+use strategy::alphabeta::Selectivity;
+use strategy::quiescence::Quiescence;
 
 pub fn argsort<T: Ord>(data: &[T]) -> Vec<usize> {
     let mut indices = (0..data.len()).collect::<Vec<_>>();
@@ -13,22 +25,46 @@ pub fn argsort<T: Ord>(data: &[T]) -> Vec<usize> {
     indices
 }
 
-fn play(white: &mut dyn Strategy, black: &mut dyn Strategy, print_board: bool, title: String) -> WinState {
-    let mut board = Board::new();
+// Plays a game from game.start, recording the moves and result into the game. With a time control,
+// the strategies are told what is left on the clocks, and a side that runs out of time loses. When
+// printing the board, how the searches are going is printed too.
+fn play(white: &mut dyn Strategy, black: &mut dyn Strategy, time_control: Option<TimeControl>, print_board: bool, mut game: Game) -> Game {
+    let title = format!("White {} vs Black {}", game.white, game.black);
+    let mut board = game.start.clone();
     let mut move_count = 0;
+    let mut clocks = [time_control.map_or(Duration::ZERO, |time_control| time_control.initial); 2];
     while board.win_state() == WinState::Playing {
         if print_board {
             println!("{}, {} to play, move {}", title, board.turn, move_count);
             user::print_termion(&board);
         }
+        let limits = SearchLimits {
+            reporter: print_board.then(|| Reporter::new(|info| println!("{}", info))),
+            ..time_control.map_or(SearchLimits::default(), |time_control| time_control.limits(clocks))
+        };
+        let start = Instant::now();
         let mv = match board.turn {
-            Color::White => white.choose_move(&board),
-            Color::Black => black.choose_move(&board)
+            Color::White => white.choose_move(&board, &limits),
+            Color::Black => black.choose_move(&board, &limits)
         };
+        // only happens once the game is over, which the loop condition already rules out
+        let Some(mv) = mv else { break };
+        if let Some(time_control) = time_control {
+            let elapsed = start.elapsed();
+            let clock = &mut clocks[board.turn as usize];
+            if elapsed > *clock {
+                println!("{} lost on time", board.turn);
+                user::print_termion(&board);
+                game.result = WinState::Winner(board.turn.opposite());
+                return game;
+            }
+            *clock = *clock - elapsed + time_control.increment;
+        }
         if print_board {
-            println!("{:?}", mv);
+            println!("{}", mv.to_san(&board));
         }
         board.apply_move(mv);
+        game.moves.push(mv);
         move_count += 1;
         if move_count > 500 {
             println!("Too long game, continuing...");
@@ -36,9 +72,23 @@ fn play(white: &mut dyn Strategy, black: &mut dyn Strategy, print_board: bool, t
         }
     }
     user::print_termion(&board);
-    board.win_state()
+    game.result = board.win_state();
+    game
 }
-fn tournament(players: &mut Vec<(String, Box<dyn FnMut() -> Box<dyn Strategy>>)>) -> Vec<i32> {
+type StrategyFactory = Box<dyn FnMut() -> Box<dyn Strategy>>;
+
+// An alpha-beta tournament entry with only some of its selective search techniques turned on
+fn selective_alphabeta(depth: u8, selectivity: alphabeta::Selectivity) -> StrategyFactory {
+    Box::new(move || {
+        let mut strategy = alphabeta::AlphaBeta::with_evaluator(depth, Positional::default());
+        strategy.selectivity = selectivity;
+        Box::new(strategy)
+    })
+}
+
+// Plays every pairing of players, appending each finished game to pgn and its positions, labeled
+// with the result, to positions
+fn tournament(players: &mut [(String, StrategyFactory)], time_control: Option<TimeControl>, pgn: &mut impl Write, positions: &mut impl Write) -> Vec<i32> {
     let mut scores = vec![0; players.len()];
     let mut white_scores = vec![0; players.len()];
     let mut black_scores = vec![0; players.len()];
@@ -48,9 +98,14 @@ fn tournament(players: &mut Vec<(String, Box<dyn FnMut() -> Box<dyn Strategy>>)>
             println!("White {} vs Black {}", players[white_index].0, players[black_index].0);
             let mut white_strategy = players[white_index].1();
             let mut black_strategy = players[black_index].1();
-            let result = play(&mut *white_strategy, &mut *black_strategy, true, format!("White {} vs Black {}", players[white_index].0, players[black_index].0));
-            println!("{:?}", result);
-            match result {
+            let mut game = Game::new(players[white_index].0.clone(), players[black_index].0.clone(), Board::new());
+            game.event = "corroded-knights tournament".into();
+            game.round = (white_index * players.len() + black_index + 1).to_string();
+            let game = play(&mut *white_strategy, &mut *black_strategy, time_control, true, game);
+            println!("{:?}", game.result);
+            writeln!(pgn, "{}", game.to_pgn()).unwrap();
+            tune::write_positions(&game, positions).unwrap();
+            match game.result {
                 WinState::Winner(Color::White) => {
                     results[white_index][black_index] = 1;
                     scores[white_index] += 1;
@@ -99,23 +154,150 @@ fn tournament(players: &mut Vec<(String, Box<dyn FnMut() -> Box<dyn Strategy>>)>
     scores
 }
 
+// Builds one of the strategies by name, for use from the command line. The alpha-beta search uses
+// the given positional evaluator.
+fn strategy_by_name(name: &str, depth: u8, positional: &Positional) -> Option<Box<dyn Strategy>> {
+    Some(match name {
+        "random" => Box::new(random::Random::seeded(rand::random())),
+        "minimax" => Box::new(minimax::BasicMinimax { depth, quiescence: None, evaluator: PieceSquare::default() }),
+        "maximax" => Box::new(minimax::Maximax { depth, evaluator: Material }),
+        "minimin" => Box::new(minimax::Minimin { depth, evaluator: Material }),
+        "singleplayer" => Box::new(minimax::SinglePlayer { depth, evaluator: Material }),
+        "additive" => Box::new(minimax::Additive { depth, evaluator: Material }),
+        "alphabeta" => Box::new(alphabeta::AlphaBeta::with_evaluator(depth, positional.clone())),
+        _ => return None,
+    })
+}
+
+// Parses a time control like "60+0.5", in seconds
+fn parse_time_control(text: &str) -> Option<TimeControl> {
+    let (initial, increment) = text.split_once('+').unwrap_or((text, "0"));
+    Some(TimeControl {
+        initial: Duration::try_from_secs_f64(initial.parse().ok()?).ok()?,
+        increment: Duration::try_from_secs_f64(increment.parse().ok()?).ok()?,
+    })
+}
+
 fn main() {
-    let mut white = minimax::Maximax { depth: 2 };
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(|arg| arg.as_str()) == Some("uci") {
+        // usage: uci [strategy] [default depth] [weights file]
+        let name = args.get(2).cloned().unwrap_or("minimax".into());
+        let default_depth = args.get(3).and_then(|depth| depth.parse().ok()).unwrap_or(2);
+        let positional = match args.get(4) {
+            Some(path) => Positional::load(path).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }),
+            None => Positional::default(),
+        };
+        if strategy_by_name(&name, default_depth, &positional).is_none() {
+            eprintln!("Unknown strategy: {}", name);
+            std::process::exit(1);
+        }
+        uci::run(&name, || strategy_by_name(&name, default_depth, &positional).unwrap());
+        return;
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("perft") {
+        // usage: perft <depth> [fen]
+        let Some(depth) = args.get(2).and_then(|depth| depth.parse().ok()) else {
+            eprintln!("Usage: perft <depth> [fen]");
+            std::process::exit(1);
+        };
+        let mut board = match args.get(3) {
+            Some(_) => Board::from_fen(&args[3..].join(" ")).unwrap_or_else(|err| {
+                eprintln!("Bad FEN: {}", err);
+                std::process::exit(1);
+            }),
+            None => Board::new(),
+        };
+        let start = std::time::Instant::now();
+        let divided = perft::divide(&mut board, depth);
+        for (mv, count) in &divided {
+            println!("{}: {}", mv.to_uci(), count);
+        }
+        let total = divided.iter().map(|(_, count)| count).sum::<u64>();
+        println!();
+        println!("Nodes searched: {} ({:?})", total, start.elapsed());
+        return;
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("eval") {
+        // usage: eval [fen]
+        let board = match args.get(2) {
+            Some(_) => Board::from_fen(&args[2..].join(" ")).unwrap_or_else(|err| {
+                eprintln!("Bad FEN: {}", err);
+                std::process::exit(1);
+            }),
+            None => Board::new(),
+        };
+        println!("{}", Positional::default().breakdown(&board));
+        return;
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("tune") {
+        // usage: tune <positions file> [weights file] [iterations]
+        let Some(path) = args.get(2) else {
+            eprintln!("Usage: tune <positions file> [weights file] [iterations]");
+            std::process::exit(1);
+        };
+        let output = args.get(3).cloned().unwrap_or("weights.txt".into());
+        let iterations = args.get(4).and_then(|iterations| iterations.parse().ok()).unwrap_or(500);
+        let positions = std::fs::File::open(path).map_err(|err| err.to_string())
+            .and_then(|file| tune::read_positions(std::io::BufReader::new(file)))
+            .unwrap_or_else(|err| {
+                eprintln!("Can't read {}: {}", path, err);
+                std::process::exit(1);
+            });
+        println!("Tuning on {} positions", positions.len());
+        let tuned = tune::tune(&Positional::default(), &positions, iterations, |iteration, error| {
+            if iteration % 50 == 0 || iteration + 1 == iterations {
+                println!("Iteration {}: error {:.6}", iteration + 1, error);
+            }
+        });
+        std::fs::write(&output, tuned.to_text()).unwrap();
+        println!("Wrote {}", output);
+        return;
+    }
+    // usage: [tournament [<seconds>+<increment>]], where without a time control every player
+    // searches to its own depth
+    let time_control = match args.get(1).map(|arg| arg.as_str()) {
+        Some("tournament") => args.get(2).map(|text| parse_time_control(text).unwrap_or_else(|| {
+            eprintln!("Bad time control: {}", text);
+            std::process::exit(1);
+        })),
+        _ => None,
+    };
+    let mut white = minimax::Maximax { depth: 2, evaluator: Material };
     let mut black = random::Random::seeded(2);
-    println!("{:?}", play(&mut white, &mut black, true, "Test Game".into()));
-    let mut players: Vec<(String, Box<dyn FnMut() -> Box<dyn Strategy>>)> = vec![
+    let game = Game::new("Maximax(depth=2)".into(), "Random(seed=2)".into(), Board::new());
+    println!("{:?}", play(&mut white, &mut black, None, true, game).result);
+    let mut players: Vec<(String, StrategyFactory)> = vec![
         ("Random(seed=42)".into(), Box::new(|| Box::new(random::Random::seeded(42)))),
         ("Random(seed=314)".into(), Box::new(|| Box::new(random::Random::seeded(314)))),
         ("Random(seed=1337)".into(), Box::new(|| Box::new(random::Random::seeded(1337)))),
         ("Random(seed=2)".into(), Box::new(|| Box::new(random::Random::seeded(2)))),
-        ("Minimax(depth=0)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 0 }))),
-        ("Minimax(depth=1)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 1 }))),
-        ("Minimax(depth=2)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 2 }))),
-        ("Maximax(depth=2)".into(), Box::new(|| Box::new(minimax::Maximax { depth: 2 }))),
-        ("Minimin(depth=2)".into(), Box::new(|| Box::new(minimax::Minimin { depth: 2 }))),
-        ("SinglePlayer(depth=2)".into(), Box::new(|| Box::new(minimax::SinglePlayer { depth: 2 }))),
-        ("Additive(depth=1)".into(), Box::new(|| Box::new(minimax::Additive { depth: 1 }))),
-        ("Additive(depth=2)".into(), Box::new(|| Box::new(minimax::Additive { depth: 2 }))),
+        ("Minimax(depth=0)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 0, quiescence: None, evaluator: Material }))),
+        ("Minimax(depth=1)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 1, quiescence: None, evaluator: Material }))),
+        ("Minimax(depth=2)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 2, quiescence: None, evaluator: Material }))),
+        ("Minimax(depth=1,quiescence=on)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 1, quiescence: Some(Quiescence::new()), evaluator: Material }))),
+        ("Maximax(depth=2)".into(), Box::new(|| Box::new(minimax::Maximax { depth: 2, evaluator: Material }))),
+        ("Minimin(depth=2)".into(), Box::new(|| Box::new(minimax::Minimin { depth: 2, evaluator: Material }))),
+        ("SinglePlayer(depth=2)".into(), Box::new(|| Box::new(minimax::SinglePlayer { depth: 2, evaluator: Material }))),
+        ("Additive(depth=1)".into(), Box::new(|| Box::new(minimax::Additive { depth: 1, evaluator: Material }))),
+        ("Additive(depth=2)".into(), Box::new(|| Box::new(minimax::Additive { depth: 2, evaluator: Material }))),
+        ("AlphaBeta(depth=3)".into(), Box::new(|| Box::new(alphabeta::AlphaBeta::new(3)))),
+        ("AlphaBeta(depth=3,eval=material+mobility)".into(), Box::new(|| Box::new(alphabeta::AlphaBeta::with_evaluator(3, (Material, Mobility { weight: 5 }))))),
+        ("Minimax(depth=2,eval=pst)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 2, quiescence: None, evaluator: PieceSquare::default() }))),
+        ("AlphaBeta(depth=3,eval=pst)".into(), Box::new(|| Box::new(alphabeta::AlphaBeta::with_evaluator(3, PieceSquare::default())))),
+        ("AlphaBeta(depth=3,eval=positional)".into(), Box::new(|| Box::new(alphabeta::AlphaBeta::with_evaluator(3, Positional::default())))),
+        ("AlphaBeta(depth=4)".into(), selective_alphabeta(4, Selectivity::ALL)),
+        ("AlphaBeta(depth=4,null=off)".into(), selective_alphabeta(4, Selectivity { null_move: false, ..Selectivity::ALL })),
+        ("AlphaBeta(depth=4,lmr=off)".into(), selective_alphabeta(4, Selectivity { late_move_reductions: false, ..Selectivity::ALL })),
+        ("AlphaBeta(depth=4,futility=off)".into(), selective_alphabeta(4, Selectivity { futility: false, reverse_futility: false, ..Selectivity::ALL })),
+        ("AlphaBeta(depth=4,extensions=off)".into(), selective_alphabeta(4, Selectivity { check_extensions: false, ..Selectivity::ALL })),
+        ("AlphaBeta(depth=4,selectivity=off)".into(), selective_alphabeta(4, Selectivity::NONE)),
+        ("Minimax(depth=2,eval=material+noise)".into(), Box::new(|| Box::new(minimax::BasicMinimax { depth: 2, quiescence: None, evaluator: (Material, Noise { seed: 7, amplitude: 50 }) }))),
     ];
-    println!("{:?}", tournament(&mut players));
+    let mut pgn = std::fs::File::create("tournament.pgn").unwrap();
+    let mut positions = std::fs::File::create("tournament.positions").unwrap();
+    println!("{:?}", tournament(&mut players, time_control, &mut pgn, &mut positions));
 }
@@ -0,0 +1,119 @@
+use crate::board::*;
+
+// Counts the leaf nodes of the legal move tree to the given depth, for checking move generation
+// against known results
+pub fn perft(board: &mut Board, depth: u8) -> u64 {
+    let mut lists = vec![MoveList::new(); depth as usize];
+    perft_with(board, depth, &mut lists)
+}
+
+// Fills one move list per remaining ply rather than a new one at every node
+fn perft_with(board: &mut Board, depth: u8, lists: &mut [MoveList]) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let (moves, lists) = lists.split_last_mut().unwrap();
+    moves.clear();
+    board.generate_moves(Stage::All, moves);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mv in moves.iter() {
+        let undo = board.make_move(*mv);
+        nodes += perft_with(board, depth-1, lists);
+        board.unmake_move(*mv, undo);
+    }
+    nodes
+}
+
+// The perft count below each legal move, which helps narrow down where two move generators disagree
+pub fn divide(board: &mut Board, depth: u8) -> Vec<(Move, u64)> {
+    board.all_moves().into_iter().map(|mv| {
+        (mv, board.consider_move(mv, |new_board| perft(new_board, depth.saturating_sub(1))))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(fen: &str, counts: &[u64]) {
+        let mut board = Board::from_fen(fen).unwrap();
+        let before = board.clone();
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(perft(&mut board, depth as u8 + 1), *count, "depth {} of {}", depth + 1, fen);
+        }
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn start_position() {
+        check(Board::STARTING_FEN, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        check("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn rook_endgame_with_en_passant_pins() {
+        check("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn promotions_and_castling_rights() {
+        check("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]);
+        check("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn promotion_captures() {
+        check("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn middlegame() {
+        check("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890]);
+    }
+
+    #[test]
+    fn divide_adds_up() {
+        let mut board = Board::new();
+        let divided = divide(&mut board, 3);
+        assert_eq!(divided.len(), 20);
+        assert_eq!(divided.iter().map(|(_, count)| count).sum::<u64>(), 8902);
+    }
+
+    #[test]
+    fn stages_split_all_moves() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            // in check from a pawn that can be taken en passant, and from a bishop that can be blocked
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+            "4k3/8/8/8/1b6/8/8/R3K2R w KQ - 0 1",
+        ];
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+            let all = board.all_moves();
+            let mut staged = MoveList::new();
+            board.generate_moves(Stage::Captures, &mut staged);
+            let captures = staged.len();
+            board.generate_moves(Stage::Quiets, &mut staged);
+            assert_eq!(staged.len(), all.len(), "{}", fen);
+            assert!(staged[..captures].iter().all(|mv| mv.attack.is_some() || mv.promote.is_some()), "{}", fen);
+            assert!(all.iter().all(|mv| staged.contains(mv)), "{}", fen);
+            let mut evasions = MoveList::new();
+            board.generate_moves(Stage::Evasions, &mut evasions);
+            if board.is_in_check(board.turn) {
+                assert_eq!(evasions.len(), all.len(), "{}", fen);
+            }
+            else {
+                assert!(evasions.is_empty(), "{}", fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::board::*;
+
+// The record of a played game, which can be written out as Portable Game Notation
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+    pub start: Board,
+    pub moves: Vec<Move>,
+    // Playing if the game was abandoned before it finished
+    pub result: WinState,
+}
+
+pub fn result_token(result: &WinState) -> &'static str {
+    match result {
+        WinState::Winner(Color::White) => "1-0",
+        WinState::Winner(Color::Black) => "0-1",
+        WinState::Draw => "1/2-1/2",
+        WinState::Playing => "*",
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Game {
+    pub fn new(white: String, black: String, start: Board) -> Game {
+        Game {
+            event: "?".into(),
+            site: "?".into(),
+            date: "????.??.??".into(),
+            round: "?".into(),
+            white,
+            black,
+            start,
+            moves: vec![],
+            result: WinState::Playing,
+        }
+    }
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        // the Seven Tag Roster, in its required order
+        for (tag, value) in [("Event", &self.event), ("Site", &self.site), ("Date", &self.date), ("Round", &self.round), ("White", &self.white), ("Black", &self.black)] {
+            pgn.push_str(&format!("[{} \"{}\"]\n", tag, escape(value)));
+        }
+        pgn.push_str(&format!("[Result \"{}\"]\n", result_token(&self.result)));
+        if self.start != Board::new() {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.start.to_fen()));
+        }
+        pgn.push('\n');
+
+        let mut tokens = vec![];
+        let mut board = self.start.clone();
+        for (i, mv) in self.moves.iter().enumerate() {
+            if board.turn == Color::White {
+                tokens.push(format!("{}.", board.fullmove_number));
+            }
+            else if i == 0 {
+                tokens.push(format!("{}...", board.fullmove_number));
+            }
+            tokens.push(mv.to_san(&board));
+            board.apply_move(*mv);
+        }
+        tokens.push(result_token(&self.result).into());
+
+        // export format keeps movetext lines under 80 characters
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 79 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        let mut board = game.start.clone();
+        for text in moves {
+            let mv = board.parse_san(text).unwrap();
+            board.apply_move(mv);
+            game.moves.push(mv);
+        }
+        game.result = board.win_state();
+    }
+
+    #[test]
+    fn exports_a_short_game() {
+        let mut game = Game::new("Fool \"the\" Player".into(), "Black".into(), Board::new());
+        game.event = "Test".into();
+        play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(game.to_pgn(), concat!(
+            "[Event \"Test\"]\n",
+            "[Site \"?\"]\n",
+            "[Date \"????.??.??\"]\n",
+            "[Round \"?\"]\n",
+            "[White \"Fool \\\"the\\\" Player\"]\n",
+            "[Black \"Black\"]\n",
+            "[Result \"0-1\"]\n",
+            "\n",
+            "1. f3 e5 2. g4 Qh4# 0-1\n",
+        ));
+    }
+
+    #[test]
+    fn exports_a_game_from_a_position() {
+        let start = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 0 30").unwrap();
+        let mut game = Game::new("White".into(), "Black".into(), start);
+        play(&mut game, &["Kd7", "O-O-O+"]);
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 30\"]\n\n"));
+        assert!(pgn.ends_with("\n30... Kd7 31. O-O-O+ *\n"));
+    }
+
+    #[test]
+    fn wraps_long_movetext() {
+        let mut game = Game::new("White".into(), "Black".into(), Board::new());
+        play(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"].repeat(4)[..14]);
+        let pgn = game.to_pgn();
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() < 80));
+    }
+}
@@ -1,9 +1,23 @@
 pub mod user;
 pub mod random;
 pub mod minimax;
+pub mod alphabeta;
+pub mod quiescence;
+pub mod ordering;
+pub mod transposition;
+pub mod limits;
+pub mod background;
+pub mod info;
 
 use crate::board::*;
+pub use limits::{SearchLimits, StopFlag, TimeBudget, TimeControl};
+pub use background::BackgroundSearch;
+pub use info::{Reporter, Score, SearchInfo};
 
-pub trait Strategy {
-    fn choose_move(&mut self, board: &Board) -> Move;
+// Strategies are Send so they can search on a worker thread, see BackgroundSearch
+pub trait Strategy: Send {
+    // Strategies that can't stop a search part of the way through search to limits.depth, or their
+    // own depth, whatever the clock or the stop flag says. The ones that can return the best move
+    // they have found so far. Returns None if there are no legal moves.
+    fn choose_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move>;
 }
\ No newline at end of file
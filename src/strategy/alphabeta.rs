@@ -0,0 +1,379 @@
+use std::time::Instant;
+use crate::board::*;
+use crate::strategy::{Score, SearchInfo, SearchLimits, StopFlag, Strategy};
+use crate::eval::{Evaluation, Evaluator, Material, flip, WIN};
+use crate::strategy::ordering::MoveOrdering;
+use crate::strategy::quiescence::Quiescence;
+use crate::strategy::transposition::*;
+
+// Selective search techniques, which search some moves less (or more) deeply than others. Each can
+// be turned off to measure what it contributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selectivity {
+    // Gives the opponent a free move, and if they still can't reach beta, assumes a real move won't
+    // let them either. Skipped without pieces besides pawns, where passing can be an advantage.
+    pub null_move: bool,
+    // Searches quiet moves late in the ordering less deeply, re-searching them if they turn out good
+    pub late_move_reductions: bool,
+    // Skips quiet moves near the leaves when the position is so far below alpha that they can't help
+    pub futility: bool,
+    // Stops searching near the leaves when the position is so far above beta that it won't drop below
+    pub reverse_futility: bool,
+    // Searches one ply deeper when in check, since the replies are few and forced
+    pub check_extensions: bool,
+}
+impl Selectivity {
+    pub const ALL: Selectivity = Selectivity { null_move: true, late_move_reductions: true, futility: true, reverse_futility: true, check_extensions: true };
+    pub const NONE: Selectivity = Selectivity { null_move: false, late_move_reductions: false, futility: false, reverse_futility: false, check_extensions: false };
+}
+
+// How much a position may be off from its static evaluation at each remaining depth, for futility
+// and reverse futility pruning
+const FUTILITY_MARGINS: [Evaluation; 4] = [0, 150, 300, 450];
+// How much shallower the search after a null move is
+const NULL_MOVE_REDUCTION: u8 = 2;
+// How many moves are searched at full depth before late move reductions start
+const FULL_DEPTH_MOVES: usize = 3;
+// How deep to go when only the clock or the node count limits the search
+const MAX_DEPTH: u8 = 64;
+// How many nodes to search between looks at the clock and the stop flag
+const CHECK_INTERVAL: u64 = 1024;
+
+// Searches the game tree with negamax and alpha-beta pruning, deepening one ply at a time until
+// it reaches the depth it was given, runs out of its node or time budget or is told to stop
+pub struct AlphaBeta<E: Evaluator = Material> {
+    // The depth to search to when the limits don't say otherwise
+    pub depth: u8,
+    pub selectivity: Selectivity,
+    pub evaluator: E,
+    // Searched at the leaves if set, otherwise they are evaluated as they stand
+    pub quiescence: Option<Quiescence>,
+    // The best line found by the last completed iteration of the last search
+    pub principal_variation: Vec<Move>,
+    // Kept between moves, since the positions searched for one move often come up again
+    pub table: TranspositionTable,
+    // Kept between moves like the table, though the history fades with each search
+    pub ordering: MoveOrdering,
+    // Indexed by ply, see MoveList
+    move_lists: Vec<MoveList>,
+    // The ply of the position reached by the null move being searched, if any
+    null_move_ply: Option<usize>,
+    nodes: u64,
+    seldepth: usize,
+    max_nodes: Option<u64>,
+    // The node count at which to next check the clock and the stop flag
+    next_check: u64,
+    deadline: Option<Instant>,
+    stop: StopFlag,
+    aborted: bool,
+}
+
+impl AlphaBeta {
+    pub fn new(depth: u8) -> AlphaBeta {
+        AlphaBeta::with_evaluator(depth, Material)
+    }
+}
+
+impl<E: Evaluator> AlphaBeta<E> {
+    pub fn with_evaluator(depth: u8, evaluator: E) -> AlphaBeta<E> {
+        AlphaBeta {
+            depth,
+            selectivity: Selectivity::ALL,
+            evaluator,
+            quiescence: Some(Quiescence::new()),
+            principal_variation: vec![],
+            table: TranspositionTable::new(1 << 16),
+            ordering: MoveOrdering::new(),
+            move_lists: vec![],
+            null_move_ply: None,
+            nodes: 0,
+            seldepth: 0,
+            max_nodes: None,
+            next_check: 0,
+            deadline: None,
+            stop: StopFlag::default(),
+            aborted: false,
+        }
+    }
+    fn out_of_budget(&mut self) -> bool {
+        if self.max_nodes.is_some_and(|max_nodes| self.nodes >= max_nodes) {
+            self.aborted = true;
+        }
+        // checking the clock and the stop flag is comparatively slow, so only do it every so often.
+        // Quiescence nodes are added in bulk, so the count can jump past any particular number.
+        if self.nodes >= self.next_check {
+            self.next_check = self.nodes + CHECK_INTERVAL;
+            if self.stop.is_stopped() || self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                self.aborted = true;
+            }
+        }
+        self.aborted
+    }
+    // Returns the score from the perspective of the side to move, filling pv with the best line found
+    fn negamax(&mut self, board: &mut Board, mut depth: u8, ply: usize, mut alpha: Evaluation, beta: Evaluation, pv: &mut Vec<Move>) -> Evaluation {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        pv.clear();
+        if self.out_of_budget() {
+            return 0;
+        }
+        // going back to an earlier position can't gain anything, so treat it as a draw right away
+        if ply > 0 && board.is_repetition() {
+            return 0;
+        }
+        let in_check = board.is_in_check(board.turn);
+        if self.move_lists.len() <= ply {
+            self.move_lists.resize_with(ply + 1, MoveList::new);
+        }
+        // checkmate and stalemate show up as having no moves, once they are generated. A draw rule
+        // doesn't save a side that is checkmated, but only evasions are needed to tell. At the root
+        // the game isn't over until someone claims the draw, so a move is still needed.
+        if ply > 0 && board.is_drawn_by_rule() {
+            let moves = &mut self.move_lists[ply];
+            moves.clear();
+            if in_check {
+                board.generate_moves(Stage::Evasions, moves);
+            }
+            return if in_check && moves.is_empty() { -(WIN - ply as Evaluation) } else { 0 };
+        }
+        if in_check && self.selectivity.check_extensions {
+            depth += 1;
+        }
+        if depth == 0 {
+            return match &mut self.quiescence {
+                Some(quiescence) => {
+                    let score = quiescence.search(board, &self.evaluator, ply, alpha, beta);
+                    self.nodes += std::mem::take(&mut quiescence.nodes);
+                    self.seldepth = self.seldepth.max(std::mem::take(&mut quiescence.seldepth));
+                    score
+                }
+                None => {
+                    // a leaf in check is only worth generating moves for to see if it is checkmate
+                    let moves = &mut self.move_lists[ply];
+                    moves.clear();
+                    if in_check {
+                        board.generate_moves(Stage::Evasions, moves);
+                    }
+                    if in_check && moves.is_empty() {
+                        -(WIN - ply as Evaluation)
+                    }
+                    else {
+                        flip(board.turn) * self.evaluator.evaluate(board)
+                    }
+                }
+            };
+        }
+        let original_alpha = alpha;
+        let entry = self.table.probe(board.hash);
+        if let Some(entry) = entry {
+            let score = score_from_table(entry.score, ply);
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            // at the root we still need a move and a principal variation, so always search it
+            if entry.depth >= depth && ply > 0 && usable {
+                pv.extend(entry.best_move);
+                return score;
+            }
+        }
+        // the shortcuts below risk missing something, so keep them out of the principal variation. They
+        // compare the static evaluation with the window, which means nothing once mates are involved.
+        let pv_node = beta - alpha > 1;
+        let prunable = !pv_node && !in_check && ply > 0 && alpha.abs() < WIN / 2 && beta.abs() < WIN / 2;
+        let static_eval = if prunable { flip(board.turn) * self.evaluator.evaluate(board) } else { 0 };
+        let near_leaves = (depth as usize) < FUTILITY_MARGINS.len();
+        if prunable && self.selectivity.reverse_futility && near_leaves && static_eval - FUTILITY_MARGINS[depth as usize] >= beta {
+            return beta;
+        }
+        let pieces = board.color_bitboard(board.turn) & !board.kind_bitboard(Kind::Pawn) & !board.kind_bitboard(Kind::King);
+        // two null moves in a row would just search the same position with less depth
+        let after_null = self.null_move_ply == Some(ply);
+        if prunable && self.selectivity.null_move && !after_null && depth > NULL_MOVE_REDUCTION && pieces != 0 && static_eval >= beta {
+            let undo = board.make_null_move();
+            let outer = self.null_move_ply.replace(ply + 1);
+            let score = -self.negamax(board, depth - 1 - NULL_MOVE_REDUCTION, ply+1, -beta, -beta + 1, &mut vec![]);
+            self.null_move_ply = outer;
+            board.unmake_null_move(undo);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+        }
+        let futile = prunable && self.selectivity.futility && near_leaves && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha;
+        let moves = &mut self.move_lists[ply];
+        moves.clear();
+        board.generate_moves(Stage::All, moves);
+        if moves.is_empty() {
+            // prefer quicker wins and slower losses
+            return if in_check { -(WIN - ply as Evaluation) } else { 0 };
+        }
+        // the table's or the previous iteration's choice is likely to still be good
+        let hint = entry.and_then(|entry| entry.best_move).or(self.principal_variation.get(ply).copied());
+        self.ordering.order(moves, hint, ply);
+        let mut child_pv = vec![];
+        for ix in 0..self.move_lists[ply].len() {
+            let mv = self.move_lists[ply][ix];
+            let undo = board.make_move(mv);
+            // moves that capture, promote or give check change too much to be judged early
+            let quiet = MoveOrdering::is_quiet(&mv) && !board.is_in_check(board.turn);
+            if futile && quiet && ix > 0 {
+                board.unmake_move(mv, undo);
+                continue;
+            }
+            let reduce = self.selectivity.late_move_reductions && quiet && !in_check && depth >= 3 && ix >= FULL_DEPTH_MOVES;
+            let mut score = alpha + 1;
+            if reduce {
+                score = -self.negamax(board, depth-2, ply+1, -alpha - 1, -alpha, &mut child_pv);
+            }
+            // without a reduction, or when the reduced search suggests the move is better than expected
+            if score > alpha {
+                score = -self.negamax(board, depth-1, ply+1, -beta, -alpha, &mut child_pv);
+            }
+            board.unmake_move(mv, undo);
+            if self.aborted {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend(child_pv.iter());
+                if alpha >= beta {
+                    self.ordering.cutoff(mv, depth, ply);
+                    break;
+                }
+            }
+        }
+        let bound = if alpha <= original_alpha {
+            Bound::Upper
+        }
+        else if alpha >= beta {
+            Bound::Lower
+        }
+        else {
+            Bound::Exact
+        };
+        self.table.store(Entry {
+            hash: board.hash,
+            depth,
+            bound,
+            score: score_to_table(alpha, ply),
+            best_move: pv.first().copied(),
+        });
+        alpha
+    }
+    // Returns None if there are no legal moves
+    pub fn search(&mut self, board: &Board, limits: &SearchLimits) -> Option<(Move, Evaluation)> {
+        let start = Instant::now();
+        let mut board = board.clone();
+        let max_depth = limits.depth.unwrap_or(if limits.is_open_ended() { MAX_DEPTH } else { self.depth });
+        let budget = limits.time_budget(board.turn);
+        self.nodes = 0;
+        self.seldepth = 0;
+        self.max_nodes = limits.nodes;
+        self.next_check = 0;
+        self.aborted = false;
+        self.deadline = budget.map(|budget| start + budget.limit);
+        self.stop = limits.stop.clone();
+        self.principal_variation.clear();
+        self.ordering.age();
+        // fall back on any legal move in case not even the first iteration finishes
+        let mut best = (*board.all_moves().first()?, 0);
+        for depth in 1..=max_depth.max(1) {
+            let mut pv = vec![];
+            let score = self.negamax(&mut board, depth, 0, -WIN - 1, WIN + 1, &mut pv);
+            if self.aborted || pv.is_empty() {
+                break;
+            }
+            best = (pv[0], score);
+            self.principal_variation = pv;
+            limits.report(|| SearchInfo {
+                depth,
+                seldepth: self.seldepth,
+                score: Score::from_evaluation(score),
+                nodes: Some(self.nodes),
+                time: start.elapsed(),
+                principal_variation: self.principal_variation.clone(),
+            });
+            // the next iteration would most likely run past the target, so save the time for later
+            if limits.stop.is_stopped() || budget.is_some_and(|budget| start.elapsed() > budget.target / 2) {
+                break;
+            }
+        }
+        Some(best)
+    }
+}
+
+impl<E: Evaluator> Strategy for AlphaBeta<E> {
+    fn choose_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        self.search(board, limits).map(|(mv, _)| mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn finds_mates_with_and_without_selectivity() {
+        for selectivity in [Selectivity::ALL, Selectivity::NONE] {
+            let mut strategy = AlphaBeta::new(4);
+            strategy.selectivity = selectivity;
+            let back_rank = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+            assert_eq!(strategy.search(&back_rank, &SearchLimits::default()), Some((back_rank.parse_uci_move("a1a8").unwrap(), WIN - 1)));
+            // mating takes a quiet king move first
+            let king_and_rook = Board::from_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1").unwrap();
+            assert_eq!(strategy.search(&king_and_rook, &SearchLimits::default()).unwrap().1, WIN - 3);
+        }
+    }
+
+    #[test]
+    fn scores_checkmate_and_stalemate_without_legal_moves() {
+        // the second mate comes on the 100th halfmove, which the fifty-move rule doesn't override
+        let mated = ["k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", "R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80"].map(|fen| Board::from_fen(fen).unwrap());
+        let stalemated = Board::from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        for quiescence in [Some(Quiescence::new()), None] {
+            let mut strategy = AlphaBeta::new(3);
+            strategy.quiescence = quiescence;
+            for (board, depth) in mated.iter().flat_map(|board| (0..3).map(move |depth| (board, depth))) {
+                let score = strategy.negamax(&mut board.clone(), depth, 0, -WIN - 1, WIN + 1, &mut vec![]);
+                assert_eq!(score, -WIN, "depth {} of {}", depth, board.to_fen());
+            }
+            for depth in 1..3 {
+                let score = strategy.negamax(&mut stalemated.clone(), depth, 0, -WIN - 1, WIN + 1, &mut vec![]);
+                assert_eq!(score, 0, "depth {}", depth);
+            }
+        }
+    }
+
+    #[test]
+    fn plays_on_at_a_root_drawn_by_rule() {
+        // the fifty-move rule has run out, but the game goes on until someone claims the draw
+        let board = Board::from_fen("4k3/8/8/8/8/8/3q4/R3K3 w - - 100 80").unwrap();
+        let (mv, _) = AlphaBeta::new(3).search(&board, &SearchLimits::default()).unwrap();
+        assert_eq!(mv, board.parse_uci_move("e1d2").unwrap());
+    }
+
+    #[test]
+    fn has_no_move_without_legal_moves() {
+        for fen in ["k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(AlphaBeta::new(3).search(&board, &SearchLimits::default()), None);
+        }
+    }
+
+    #[test]
+    fn stops_at_the_time_limit() {
+        let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let limits = SearchLimits { movetime: Some(Duration::from_millis(100)), ..SearchLimits::default() };
+        let start = Instant::now();
+        let (mv, _) = AlphaBeta::new(3).search(&board, &limits).unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500), "{:?}", start.elapsed());
+        assert!(board.legal_moves().contains(&mv));
+    }
+}
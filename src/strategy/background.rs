@@ -0,0 +1,70 @@
+use std::thread::{self, JoinHandle};
+use crate::board::*;
+use crate::strategy::{SearchLimits, StopFlag, Strategy};
+
+// A search running on a worker thread, so whoever started it can keep going and stop it early.
+// The strategy is handed back once the search is over, keeping whatever it has learned.
+pub struct BackgroundSearch {
+    stop: StopFlag,
+    handle: JoinHandle<(Box<dyn Strategy>, Option<Move>)>,
+}
+
+impl BackgroundSearch {
+    // Starts choosing a move, calling done with it on the worker thread as soon as it is chosen, or
+    // with None if there are no legal moves
+    pub fn start(mut strategy: Box<dyn Strategy>, board: Board, limits: SearchLimits, done: impl FnOnce(Option<Move>) + Send + 'static) -> BackgroundSearch {
+        let stop = limits.stop.clone();
+        let handle = thread::spawn(move || {
+            let mv = strategy.choose_move(&board, &limits);
+            done(mv);
+            (strategy, mv)
+        });
+        BackgroundSearch { stop, handle }
+    }
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+    // Waits for the search to finish by itself
+    pub fn wait(self) -> (Box<dyn Strategy>, Option<Move>) {
+        self.handle.join().expect("search thread panicked")
+    }
+    // Tells the search to stop and waits for the best move it has found so far
+    pub fn stop(self) -> (Box<dyn Strategy>, Option<Move>) {
+        self.stop.stop();
+        self.wait()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use crate::eval::Material;
+    use crate::strategy::alphabeta::AlphaBeta;
+    use crate::strategy::minimax::{Additive, BasicMinimax, Maximax, Minimin, SinglePlayer};
+    use crate::strategy::quiescence::Quiescence;
+
+    // Starts an infinite search, stops it after a while and checks it comes back quickly with a move
+    fn stop_after_a_while(strategy: Box<dyn Strategy>) {
+        let board = Board::new();
+        let limits = SearchLimits { infinite: true, ..SearchLimits::default() };
+        let search = BackgroundSearch::start(strategy, board.clone(), limits, |_| {});
+        thread::sleep(Duration::from_millis(50));
+        assert!(!search.is_finished());
+        let start = Instant::now();
+        let (_, mv) = search.stop();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(board.legal_moves().contains(&mv.unwrap()));
+    }
+
+    #[test]
+    fn stops_with_a_legal_move() {
+        stop_after_a_while(Box::new(AlphaBeta::new(64)));
+        // searches far too deep to finish by themselves
+        stop_after_a_while(Box::new(BasicMinimax { depth: 8, quiescence: Some(Quiescence::new()), evaluator: Material }));
+        stop_after_a_while(Box::new(Maximax { depth: 8, evaluator: Material }));
+        stop_after_a_while(Box::new(Minimin { depth: 8, evaluator: Material }));
+        stop_after_a_while(Box::new(SinglePlayer { depth: 8, evaluator: Material }));
+        stop_after_a_while(Box::new(Additive { depth: 8, evaluator: Material }));
+    }
+}
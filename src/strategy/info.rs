@@ -0,0 +1,125 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::board::*;
+use crate::eval::{Evaluation, WIN};
+
+// Scores within this many plies of WIN are checkmates
+const MAX_MATE_PLY: Evaluation = 1000;
+
+// A score from the perspective of the side to move, as UCI reports them
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Score {
+    Centipawns(Evaluation),
+    // Moves until checkmate, negative when the side to move is the one getting mated
+    Mate(i32),
+}
+
+impl Score {
+    // Reads a score where a checkmate n plies from the root is worth WIN - n, as in AlphaBeta
+    pub fn from_evaluation(evaluation: Evaluation) -> Score {
+        let plies = WIN - evaluation.abs();
+        if (0..MAX_MATE_PLY).contains(&plies) {
+            Score::Mate(evaluation.signum() * (plies + 1) / 2)
+        }
+        else {
+            Score::Centipawns(evaluation)
+        }
+    }
+}
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Score::Centipawns(centipawns) => write!(f, "cp {}", centipawns),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+// How a search is going, reported after each iteration by strategies that deepen iteratively and
+// once at the end by the others. Nodes are only reported by strategies that count them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SearchInfo {
+    pub depth: u8,
+    // The deepest ply reached, counting quiescence search and extensions
+    pub seldepth: usize,
+    pub score: Score,
+    pub nodes: Option<u64>,
+    pub time: Duration,
+    // The best line found, starting with the move that would be played
+    pub principal_variation: Vec<Move>,
+}
+
+impl SearchInfo {
+    pub fn nodes_per_second(&self) -> Option<u64> {
+        let nodes = self.nodes?;
+        Some((nodes as f64 / self.time.as_secs_f64().max(0.001)) as u64)
+    }
+}
+// Formats the info in the words of a UCI "info" command
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "depth {} seldepth {} score {}", self.depth, self.seldepth, self.score)?;
+        if let (Some(nodes), Some(nps)) = (self.nodes, self.nodes_per_second()) {
+            write!(f, " nodes {} nps {}", nodes, nps)?;
+        }
+        write!(f, " time {}", self.time.as_millis())?;
+        if !self.principal_variation.is_empty() {
+            write!(f, " pv")?;
+            for mv in &self.principal_variation {
+                write!(f, " {}", mv.to_uci())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Called with each SearchInfo as it is reported, on whichever thread is searching
+#[derive(Clone)]
+pub struct Reporter(Arc<dyn Fn(&SearchInfo) + Send + Sync>);
+
+impl Reporter {
+    pub fn new(report: impl Fn(&SearchInfo) + Send + Sync + 'static) -> Reporter {
+        Reporter(Arc::new(report))
+    }
+    pub fn report(&self, info: &SearchInfo) {
+        (self.0)(info)
+    }
+}
+impl fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Reporter")
+    }
+}
+// Compared by identity, like StopFlag
+impl PartialEq for Reporter {
+    fn eq(&self, other: &Reporter) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for Reporter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_and_info() {
+        assert_eq!(Score::from_evaluation(35), Score::Centipawns(35));
+        assert_eq!(Score::from_evaluation(WIN - 1), Score::Mate(1));
+        assert_eq!(Score::from_evaluation(WIN - 3), Score::Mate(2));
+        assert_eq!(Score::from_evaluation(-(WIN - 2)), Score::Mate(-1));
+        let board = Board::new();
+        let info = SearchInfo {
+            depth: 2,
+            seldepth: 4,
+            score: Score::Centipawns(-15),
+            nodes: Some(3000),
+            time: Duration::from_millis(1500),
+            principal_variation: vec![board.parse_uci_move("e2e4").unwrap()],
+        };
+        assert_eq!(info.to_string(), "depth 2 seldepth 4 score cp -15 nodes 3000 nps 2000 time 1500 pv e2e4");
+        let info = SearchInfo { nodes: None, principal_variation: vec![], ..info };
+        assert_eq!(info.to_string(), "depth 2 seldepth 4 score cp -15 time 1500");
+    }
+}
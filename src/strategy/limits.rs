@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::board::*;
+use crate::strategy::{Reporter, SearchInfo};
+
+// Time kept back from each move for the overhead of passing it on
+const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+// How many more moves the clock is assumed to need to last for when there's no movestogo
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+// What a strategy may spend on choosing a move, as in the UCI "go" command. Everything left out is
+// unlimited, and a strategy without any limits searches to its own default depth.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    // Exactly how long to spend on this move
+    pub movetime: Option<Duration>,
+    // What is left on each side's clock, and what they gain after each move
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    // How many moves are left until the next time control, if it isn't the whole game
+    pub movestogo: Option<u32>,
+    // Search until told to stop
+    pub infinite: bool,
+    // Set from another thread to end the search early
+    pub stop: StopFlag,
+    // Told how the search is going, if set
+    pub reporter: Option<Reporter>,
+}
+
+// A flag shared between a search and whoever started it. Clones share the same flag.
+#[derive(Debug, Default, Clone)]
+pub struct StopFlag(Arc<AtomicBool>);
+
+impl StopFlag {
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+// Flags are compared by identity, since a flag is only the same as its own clones
+impl PartialEq for StopFlag {
+    fn eq(&self, other: &StopFlag) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for StopFlag {}
+
+// How long a search should take. Iterative deepening shouldn't start a new iteration past half the
+// target, since the next one usually takes longer than all the previous ones together, and has to
+// stop at the limit.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TimeBudget {
+    pub target: Duration,
+    pub limit: Duration,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> SearchLimits {
+        SearchLimits { depth: Some(depth), ..SearchLimits::default() }
+    }
+    // Whether the search is bounded by anything besides depth. A stop flag alone doesn't count, so
+    // an unlimited search that can be stopped still ends at the strategy's own depth.
+    pub fn is_open_ended(&self) -> bool {
+        self.nodes.is_some() || self.movetime.is_some() || self.wtime.is_some() || self.btime.is_some() || self.infinite
+    }
+    // Passes the info on to the reporter, if there is one
+    pub fn report(&self, info: impl FnOnce() -> SearchInfo) {
+        if let Some(reporter) = &self.reporter {
+            reporter.report(&info());
+        }
+    }
+    // How long the given side should spend on its move: the movetime if there is one, and otherwise
+    // an even share of its clock over the moves left plus most of its increment
+    pub fn time_budget(&self, color: Color) -> Option<TimeBudget> {
+        if self.infinite {
+            return None;
+        }
+        if let Some(movetime) = self.movetime {
+            return Some(TimeBudget { target: movetime, limit: movetime });
+        }
+        let (time, increment) = match color {
+            Color::White => (self.wtime?, self.winc.unwrap_or_default()),
+            Color::Black => (self.btime?, self.binc.unwrap_or_default()),
+        };
+        let available = time.saturating_sub(MOVE_OVERHEAD);
+        let moves_left = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let target = (available / moves_left + increment * 3 / 4).min(available);
+        // allow going over the target when an iteration is nearly done, but never use up the clock
+        let limit = (target * 3).min(available / 2).max(target);
+        Some(TimeBudget { target, limit })
+    }
+}
+
+// A clock for playing games: each side starts with initial and gains increment after each move
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TimeControl {
+    pub initial: Duration,
+    pub increment: Duration,
+}
+
+impl TimeControl {
+    // The limits for a move, given what is left on each side's clock
+    pub fn limits(&self, clocks: [Duration; 2]) -> SearchLimits {
+        SearchLimits {
+            wtime: Some(clocks[Color::White as usize]),
+            btime: Some(clocks[Color::Black as usize]),
+            winc: Some(self.increment),
+            binc: Some(self.increment),
+            ..SearchLimits::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_budget() {
+        let seconds = Duration::from_secs;
+        assert_eq!(SearchLimits::default().time_budget(Color::White), None);
+        let movetime = SearchLimits { movetime: Some(seconds(2)), ..SearchLimits::default() };
+        assert_eq!(movetime.time_budget(Color::Black), Some(TimeBudget { target: seconds(2), limit: seconds(2) }));
+        let clock = SearchLimits { wtime: Some(seconds(60)), btime: Some(seconds(1)), winc: Some(seconds(1)), ..SearchLimits::default() };
+        let white = clock.time_budget(Color::White).unwrap();
+        assert!(white.target > seconds(2) && white.target < seconds(3));
+        assert!(white.limit > white.target && white.limit < seconds(30));
+        let black = clock.time_budget(Color::Black).unwrap();
+        assert!(black.limit < seconds(1));
+        let last_move = SearchLimits { wtime: Some(seconds(10)), movestogo: Some(1), ..SearchLimits::default() };
+        let budget = last_move.time_budget(Color::White).unwrap();
+        assert!(budget.target > seconds(9) && budget.limit == budget.target);
+    }
+}
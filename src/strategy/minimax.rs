@@ -1,260 +1,303 @@
+use std::time::Instant;
 use crate::board::*;
-use crate::strategy::Strategy;
+use crate::eval::{Evaluation, Evaluator, Material, flip, WIN};
+use crate::strategy::{Score, SearchInfo, SearchLimits, StopFlag, Strategy};
+use crate::strategy::quiescence::Quiescence;
 
-// Searches the game tree for the best possible move
-pub struct BasicMinimax {
-    pub depth: u8,
+// Reads a score from the perspective of the side to move. These searches score a checkmate as WIN
+// moved away from 0 or towards it by bonus for each ply of depth they still had left when they
+// found it, so the distance to the mate can be worked out from the search depth. A move is
+// plies_per_move plies.
+fn score(evaluation: Evaluation, depth: u8, bonus: Evaluation, plies_per_move: Evaluation) -> Score {
+    let plies_left = (evaluation.abs() - WIN).abs() / bonus;
+    if plies_left > depth as Evaluation {
+        return Score::Centipawns(evaluation);
+    }
+    let plies = depth as Evaluation + 1 - plies_left;
+    Score::Mate(evaluation.signum() * (plies + plies_per_move - 1) / plies_per_move)
 }
 
-type Evaluation = i32;
-pub fn flip(color: Color) -> Evaluation {
-    match color {
-        Color::White => 1,
-        Color::Black => -1
-    }
+// The searches return None when there are no legal moves, but they only recurse into games that
+// are still going, where there always are some
+const IN_PROGRESS: &str = "a game in progress has legal moves";
+
+// What a search has been through so far, and whether it has been told to stop
+struct Progress<'a> {
+    stop: &'a StopFlag,
+    nodes: u64,
+    seldepth: usize,
+    // How many moves deep the position being searched is
+    ply: usize,
 }
 
-impl BasicMinimax {
-    fn evaluate_piece(piece: Piece) -> Evaluation {
-        use Kind::*;
-        flip(piece.color) * match piece.kind {
-            Pawn => 1,
-            Knight => 3,
-            Bishop => 3,
-            Rook => 5,
-            Queen => 9,
-            King => 4
-        }
+impl<'a> Progress<'a> {
+    fn new(stop: &'a StopFlag) -> Progress<'a> {
+        Progress { stop, nodes: 0, seldepth: 0, ply: 0 }
     }
-    fn evaluate_board(board: &Board) -> Evaluation {
-        let mut score = 0;
-        for x in 0..8 {
-            for y in 0..8 {
-                if let Some(piece) = board[Loc(x, y)] {
-                    score += BasicMinimax::evaluate_piece(piece) * 100 + (y as Evaluation)
-                }
-            }
-        }
-        score
+    // Like Board::consider_move, counting the position the move leads to
+    fn consider_move<T>(&mut self, board: &mut Board, mv: Move, f: impl FnOnce(&mut Board, &mut Progress<'a>) -> T) -> T {
+        self.nodes += 1;
+        self.ply += 1;
+        self.seldepth = self.seldepth.max(self.ply);
+        let result = board.consider_move(mv, |new_board| f(new_board, self));
+        self.ply -= 1;
+        result
     }
-    fn evaluate_recursive(depth: u8, board: &mut Board) -> Evaluation {
-        if depth == 0 || board.win_state() != WinState::Playing {
-            BasicMinimax::evaluate_board(board) + flip(board.turn) * (depth as Evaluation)
-        }
-        else {
-            BasicMinimax::search(depth-1, board).1
+}
+
+// Reports the outcome of a search
+fn report(limits: &SearchLimits, depth: u8, start: Instant, progress: &Progress, mv: Move, score: Score) -> Move {
+    limits.report(|| SearchInfo {
+        depth,
+        seldepth: progress.seldepth,
+        score,
+        nodes: Some(progress.nodes),
+        time: start.elapsed(),
+        principal_variation: vec![mv],
+    });
+    mv
+}
+
+// Searches the game tree for the best possible move
+pub struct BasicMinimax<E: Evaluator = Material> {
+    pub depth: u8,
+    // See Quiescence; without it the leaves are evaluated as they stand
+    pub quiescence: Option<Quiescence>,
+    pub evaluator: E,
+}
+
+impl<E: Evaluator> BasicMinimax<E> {
+    fn evaluate_recursive(&mut self, depth: u8, board: &mut Board, progress: &mut Progress) -> Evaluation {
+        match board.win_state() {
+            WinState::Playing if depth > 0 => self.search(depth-1, board, progress).expect(IN_PROGRESS).1,
+            WinState::Playing => match &mut self.quiescence {
+                Some(quiescence) => {
+                    // it starts counting plies from here, which keeps its mate scores in line with
+                    // the ones above
+                    let score = quiescence.search(board, &self.evaluator, 0, -WIN - 1, WIN + 1);
+                    progress.nodes += std::mem::take(&mut quiescence.nodes);
+                    progress.seldepth = progress.seldepth.max(progress.ply + std::mem::take(&mut quiescence.seldepth));
+                    flip(board.turn) * score
+                }
+                None => self.evaluator.evaluate(board),
+            },
+            // Prefer quicker wins and slower losses
+            WinState::Winner(color) => flip(color) * (WIN + depth as Evaluation),
+            state => self.evaluator.evaluate_state(board, &state),
         }
     }
-    fn search(depth: u8, board: &mut Board) -> (Move, Evaluation) {
+    fn search(&mut self, depth: u8, board: &mut Board, progress: &mut Progress) -> Option<(Move, Evaluation)> {
         let mut best = None;
-        for x in 0..8 {
-            for y in 0..8 {
-                for mv in board.get_moves_for(Loc(x, y)) {
-                    let score = board.consider_move(mv, |new_board| {
-                        BasicMinimax::evaluate_recursive(depth, new_board)
-                    });
-                    if best == None {
-                        best = Some((mv, score));
-                    }
-                    else if let Some((bmv, bscore)) = best {
-                        if bscore * flip(board.turn) < score * flip(board.turn) {
-                            best = Some((mv, score));
-                        }
-                    }
+        for mv in board.all_moves() {
+            let score = progress.consider_move(board, mv, |new_board, progress| {
+                self.evaluate_recursive(depth, new_board, progress)
+            });
+            // once stopped, the scores are cut short and say nothing, so every level returns the
+            // best move it had found before that
+            if progress.stop.is_stopped() && best.is_some() {
+                break;
+            }
+            if best.is_none() {
+                best = Some((mv, score));
+            }
+            else if let Some((_, bscore)) = best {
+                if bscore * flip(board.turn) < score * flip(board.turn) {
+                    best = Some((mv, score));
                 }
             }
         }
-        best.unwrap()
+        best
     }
 }
 
-impl Strategy for BasicMinimax {
-    fn choose_move(&mut self, board: &Board) -> Move {
+impl<E: Evaluator> Strategy for BasicMinimax<E> {
+    fn choose_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        let start = Instant::now();
         let mut board = board.clone();
-        BasicMinimax::search(self.depth, &mut board).0
+        let depth = limits.depth.unwrap_or(self.depth);
+        let mut progress = Progress::new(&limits.stop);
+        let (mv, evaluation) = self.search(depth, &mut board, &mut progress)?;
+        Some(report(limits, depth, start, &progress, mv, score(flip(board.turn) * evaluation, depth, 1, 2)))
     }
 }
 
 // Searches the game tree for the best possible move, assuming the opponent will cooperate
-pub struct Maximax {
+pub struct Maximax<E: Evaluator = Material> {
     pub depth: u8,
+    pub evaluator: E,
 }
 // Searches the game tree for the worst possible move, assuming the opponent will do its best
-pub struct Minimin {
+pub struct Minimin<E: Evaluator = Material> {
     pub depth: u8,
+    pub evaluator: E,
 }
 
-impl Maximax {
+impl<E: Evaluator> Maximax<E> {
     // Assumes it is currently the opponent's turn
-    fn evaluate(depth: u8, board: &mut Board) -> Evaluation {
-        if depth == 0 || board.win_state() != WinState::Playing {
-            -flip(board.turn) * BasicMinimax::evaluate_board(board) + 10 * (depth as Evaluation)
+    fn evaluate(evaluator: &E, depth: u8, board: &mut Board, progress: &mut Progress) -> Evaluation {
+        let state = board.win_state();
+        if depth == 0 || state != WinState::Playing {
+            -flip(board.turn) * evaluator.evaluate_state(board, &state) + 10 * (depth as Evaluation)
         }
         else {
-            -Minimin::search(depth-1, board).1
+            -Minimin::search(evaluator, depth-1, board, progress).expect(IN_PROGRESS).1
         }
     }
-    fn search(depth: u8, board: &mut Board) -> (Move, Evaluation) {
+    fn search(evaluator: &E, depth: u8, board: &mut Board, progress: &mut Progress) -> Option<(Move, Evaluation)> {
         let mut best = None;
-        for x in 0..8 {
-            for y in 0..8 {
-                for mv in board.get_moves_for(Loc(x, y)) {
-                    let score = board.consider_move(mv, |new_board| {
-                        Maximax::evaluate(depth, new_board)
-                    });
-                    if let Some((attacked, _)) = mv.attack {
-                        if attacked.kind == Kind::King {
-                        }
-                    }
-                    if best == None {
-                        best = Some((mv, score));
-                    }
-                    else if let Some((bmv, bscore)) = best {
-                        if bscore < score {
-                            best = Some((mv, score));
-                            if let Some((attacked, _)) = bmv.attack {
-                                if attacked.kind == Kind::King {
-                                }
-                            }
-                            if let Some((attacked, _)) = mv.attack {
-                                if attacked.kind == Kind::King {
-                                }
-                            }
-                        }
-                    }
+        for mv in board.all_moves() {
+            let score = progress.consider_move(board, mv, |new_board, progress| {
+                Maximax::evaluate(evaluator, depth, new_board, progress)
+            });
+            if progress.stop.is_stopped() && best.is_some() {
+                break;
+            }
+            if best.is_none() {
+                best = Some((mv, score));
+            }
+            else if let Some((_, bscore)) = best {
+                if bscore < score {
+                    best = Some((mv, score));
                 }
             }
         }
-        best.unwrap()
+        best
     }
 }
 
-impl Minimin {
+impl<E: Evaluator> Minimin<E> {
     // Assumes it is currently the opponent's turn
-    fn evaluate(depth: u8, board: &mut Board) -> Evaluation {
-        if depth == 0 || board.win_state() != WinState::Playing {
-            -flip(board.turn) * BasicMinimax::evaluate_board(board) - 10 * (depth as Evaluation)
+    fn evaluate(evaluator: &E, depth: u8, board: &mut Board, progress: &mut Progress) -> Evaluation {
+        let state = board.win_state();
+        if depth == 0 || state != WinState::Playing {
+            -flip(board.turn) * evaluator.evaluate_state(board, &state) - 10 * (depth as Evaluation)
         }
         else {
-            -Maximax::search(depth-1, board).1
+            -Maximax::search(evaluator, depth-1, board, progress).expect(IN_PROGRESS).1
         }
     }
-    fn search(depth: u8, board: &mut Board) -> (Move, Evaluation) {
+    fn search(evaluator: &E, depth: u8, board: &mut Board, progress: &mut Progress) -> Option<(Move, Evaluation)> {
         let mut best = None;
-        for x in 0..8 {
-            for y in 0..8 {
-                for mv in board.get_moves_for(Loc(x, y)) {
-                    let score = board.consider_move(mv, |new_board| {
-                        Minimin::evaluate(depth, new_board)
-                    });
-                    if best == None {
-                        best = Some((mv, score));
-                    }
-                    else if let Some((bmv, bscore)) = best {
-                        if bscore > score {
-                            best = Some((mv, score));
-                        }
-                    }
+        for mv in board.all_moves() {
+            let score = progress.consider_move(board, mv, |new_board, progress| {
+                Minimin::evaluate(evaluator, depth, new_board, progress)
+            });
+            if progress.stop.is_stopped() && best.is_some() {
+                break;
+            }
+            if best.is_none() {
+                best = Some((mv, score));
+            }
+            else if let Some((_, bscore)) = best {
+                if bscore > score {
+                    best = Some((mv, score));
                 }
             }
         }
-        best.unwrap()
+        best
     }
 }
 
-impl Strategy for Maximax {
-    fn choose_move(&mut self, board: &Board) -> Move {
+impl<E: Evaluator> Strategy for Maximax<E> {
+    fn choose_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        let start = Instant::now();
         let mut board = board.clone();
-        let (mv, evaluation) = Maximax::search(self.depth, &mut board);
-        println!("{}", evaluation);
-        mv
+        let depth = limits.depth.unwrap_or(self.depth);
+        let mut progress = Progress::new(&limits.stop);
+        let (mv, evaluation) = Maximax::search(&self.evaluator, depth, &mut board, &mut progress)?;
+        Some(report(limits, depth, start, &progress, mv, score(evaluation, depth, 10, 2)))
     }
 }
-impl Strategy for Minimin {
-    fn choose_move(&mut self, board: &Board) -> Move {
+impl<E: Evaluator> Strategy for Minimin<E> {
+    fn choose_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        let start = Instant::now();
         let mut board = board.clone();
-        Minimin::search(self.depth, &mut board).0
+        let depth = limits.depth.unwrap_or(self.depth);
+        let mut progress = Progress::new(&limits.stop);
+        let (mv, evaluation) = Minimin::search(&self.evaluator, depth, &mut board, &mut progress)?;
+        Some(report(limits, depth, start, &progress, mv, score(evaluation, depth, 10, 2)))
     }
 }
 
 // Searches the game tree for the best possible move, assuming that the opponent makes no moves
-pub struct SinglePlayer {
+pub struct SinglePlayer<E: Evaluator = Material> {
     pub depth: u8,
+    pub evaluator: E,
 }
 
-impl SinglePlayer {
-    fn evaluate(depth: u8, board: &mut Board) -> Evaluation {
-        if depth == 0 || board.win_state() != WinState::Playing {
-            flip(board.turn) * BasicMinimax::evaluate_board(board) + (depth as Evaluation)
+impl<E: Evaluator> SinglePlayer<E> {
+    fn evaluate(evaluator: &E, depth: u8, board: &mut Board, progress: &mut Progress) -> Evaluation {
+        let state = board.win_state();
+        if depth == 0 || state != WinState::Playing {
+            flip(board.turn) * evaluator.evaluate_state(board, &state) + (depth as Evaluation)
         }
         else {
-            SinglePlayer::search(depth-1, board).1
+            SinglePlayer::search(evaluator, depth-1, board, progress).expect(IN_PROGRESS).1
         }
     }
-    fn search(depth: u8, board: &mut Board) -> (Move, Evaluation) {
+    fn search(evaluator: &E, depth: u8, board: &mut Board, progress: &mut Progress) -> Option<(Move, Evaluation)> {
         let mut best = None;
-        for x in 0..8 {
-            for y in 0..8 {
-                for mv in board.get_moves_for(Loc(x, y)) {
-                    let score = board.consider_move(mv, |new_board| {
-                        new_board.turn = new_board.turn.opposite();
-                        new_board.en_passant_file = None;
-                        let s = SinglePlayer::evaluate(depth, new_board);
-                        new_board.turn = new_board.turn.opposite();
-                        s
-                    });
-                    if best == None {
-                        best = Some((mv, score));
-                    }
-                    else if let Some((bmv, bscore)) = best {
-                        if bscore < score {
-                            best = Some((mv, score));
-                        }
-                    }
+        for mv in board.all_moves() {
+            // the opponent passes, which keeps the hash and en passant file up to date
+            let score = progress.consider_move(board, mv, |new_board, progress| {
+                let undo = new_board.make_null_move();
+                let s = SinglePlayer::evaluate(evaluator, depth, new_board, progress);
+                new_board.unmake_null_move(undo);
+                s
+            });
+            if progress.stop.is_stopped() && best.is_some() {
+                break;
+            }
+            if best.is_none() {
+                best = Some((mv, score));
+            }
+            else if let Some((_, bscore)) = best {
+                if bscore < score {
+                    best = Some((mv, score));
                 }
             }
         }
-        best.unwrap()
+        best
     }
 }
 
-impl Strategy for SinglePlayer {
-    fn choose_move(&mut self, board: &Board) -> Move {
+impl<E: Evaluator> Strategy for SinglePlayer<E> {
+    fn choose_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        let start = Instant::now();
         let mut board = board.clone();
-        SinglePlayer::search(self.depth, &mut board).0
+        let depth = limits.depth.unwrap_or(self.depth);
+        let mut progress = Progress::new(&limits.stop);
+        let (mv, evaluation) = SinglePlayer::search(&self.evaluator, depth, &mut board, &mut progress)?;
+        // every ply is one of its own moves
+        Some(report(limits, depth, start, &progress, mv, score(evaluation, depth, 1, 1)))
     }
 }
 
 // Adds up the values for the expanded game tree and picks the best option among them
-pub struct Additive {
+pub struct Additive<E: Evaluator = Material> {
     pub depth: u8,
+    pub evaluator: E,
 }
 
-impl Additive {
-    fn evaluate(depth: u8, board: &mut Board, opponent: bool) -> f64 {
+impl<E: Evaluator> Additive<E> {
+    fn evaluate(evaluator: &E, depth: u8, board: &mut Board, opponent: bool, progress: &mut Progress) -> f64 {
         if depth == 0 {
-            BasicMinimax::evaluate_board(board) as f64
+            evaluator.evaluate(board) as f64
         }
-        else if board.win_state() != WinState::Playing {
-            let mut move_count = 0;
-            for x in 0..8 {
-                for y in 0..8 {
-                    move_count += board.get_moves_for(Loc(x, y)).len();
-                }
-            }
-            (BasicMinimax::evaluate_board(board) as f64) * (move_count as f64).powf(depth as f64)
+        else if let state @ (WinState::Winner(_) | WinState::Draw) = board.win_state() {
+            let move_count: usize = Squares(board.color_bitboard(board.turn)).map(|loc| board.get_moves_for(loc).len()).sum();
+            (evaluator.evaluate_state(board, &state) as f64) * (move_count as f64).powf(depth as f64)
         }
         else {
             let mut score = 0.0;
             let mut move_count = 0;
-            for x in 0..8 {
-                for y in 0..8 {
-                    for mv in board.get_moves_for(Loc(x, y)) {
-                        score += board.consider_move(mv, |new_board| {
-                            Additive::evaluate(depth-1, new_board, !opponent)
-                        });
-                        move_count += 1;
-                    }
+            for mv in board.all_moves() {
+                score += progress.consider_move(board, mv, |new_board, progress| {
+                    Additive::evaluate(evaluator, depth-1, new_board, !opponent, progress)
+                });
+                move_count += 1;
+                if progress.stop.is_stopped() {
+                    break;
                 }
             }
             if opponent {
@@ -263,34 +306,120 @@ impl Additive {
             score
         }
     }
-    fn search(depth: u8, board: &mut Board) -> (Move, f64) {
+    fn search(evaluator: &E, depth: u8, board: &mut Board, progress: &mut Progress) -> Option<(Move, f64)> {
         let mut best = None;
-        for x in 0..8 {
-            for y in 0..8 {
-                for mv in board.get_moves_for(Loc(x, y)) {
-                    let score = board.consider_move(mv, |new_board| {
-                        Additive::evaluate(depth, new_board, true)
-                    });
-                    if best == None {
-                        best = Some((mv, score));
-                    }
-                    else if let Some((bmv, bscore)) = best {
-                        if bscore * (flip(board.turn) as f64) < score * (flip(board.turn) as f64) {
-                            best = Some((mv, score));
-                        }
-                    }
+        for mv in board.all_moves() {
+            let score = progress.consider_move(board, mv, |new_board, progress| {
+                Additive::evaluate(evaluator, depth, new_board, true, progress)
+            });
+            if progress.stop.is_stopped() && best.is_some() {
+                break;
+            }
+            if best.is_none() {
+                best = Some((mv, score));
+            }
+            else if let Some((_, bscore)) = best {
+                if bscore * (flip(board.turn) as f64) < score * (flip(board.turn) as f64) {
+                    best = Some((mv, score));
                 }
             }
         }
-        best.unwrap()
+        best
     }
 }
 
-impl Strategy for Additive {
-    fn choose_move(&mut self, board: &Board) -> Move {
+impl<E: Evaluator> Strategy for Additive<E> {
+    fn choose_move(&mut self, board: &Board, limits: &SearchLimits) -> Option<Move> {
+        let start = Instant::now();
         let mut board = board.clone();
-        let (mv, evaluation) = Additive::search(self.depth, &mut board);
-        println!("Evaluation: {}", evaluation);
-        mv
+        let depth = limits.depth.unwrap_or(self.depth);
+        let mut progress = Progress::new(&limits.stop);
+        let (mv, evaluation) = Additive::search(&self.evaluator, depth, &mut board, &mut progress)?;
+        // the sums are on a scale of their own, where checkmates add nothing, but they're the
+        // closest thing to centipawns it has
+        Some(report(limits, depth, start, &progress, mv, Score::Centipawns(flip(board.turn) * evaluation.round() as Evaluation)))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::strategy::Reporter;
+
+    // What the strategy reports about its search
+    fn reported_info(strategy: &mut dyn Strategy, fen: &str) -> SearchInfo {
+        let infos = Arc::new(Mutex::new(vec![]));
+        let reported = infos.clone();
+        let limits = SearchLimits { reporter: Some(Reporter::new(move |info| reported.lock().unwrap().push(info.clone()))), ..SearchLimits::default() };
+        strategy.choose_move(&Board::from_fen(fen).unwrap(), &limits);
+        let infos = infos.lock().unwrap();
+        assert_eq!(infos.len(), 1);
+        infos[0].clone()
     }
-}
\ No newline at end of file
+    // The score the strategy reports for its move
+    fn reported_score(strategy: &mut dyn Strategy, fen: &str) -> Score {
+        reported_info(strategy, fen).score
+    }
+
+    #[test]
+    fn has_no_move_without_legal_moves() {
+        let strategies: [Box<dyn Strategy>; 5] = [
+            Box::new(BasicMinimax { depth: 2, quiescence: Some(Quiescence::new()), evaluator: Material }),
+            Box::new(Maximax { depth: 2, evaluator: Material }),
+            Box::new(Minimin { depth: 2, evaluator: Material }),
+            Box::new(SinglePlayer { depth: 2, evaluator: Material }),
+            Box::new(Additive { depth: 2, evaluator: Material }),
+        ];
+        for mut strategy in strategies {
+            for fen in ["k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"] {
+                assert_eq!(strategy.choose_move(&Board::from_fen(fen).unwrap(), &SearchLimits::default()), None);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_mates_from_scores() {
+        // BasicMinimax at depth 2, from the perspective of the side to move
+        assert_eq!(score(WIN + 2, 2, 1, 2), Score::Mate(1));
+        assert_eq!(score(WIN, 2, 1, 2), Score::Mate(2));
+        assert_eq!(score(-(WIN + 1), 2, 1, 2), Score::Mate(-1));
+        // Maximax at depth 3, where the bonus is 10 per ply
+        assert_eq!(score(WIN + 30, 3, 10, 2), Score::Mate(1));
+        assert_eq!(score(-WIN + 20, 3, 10, 2), Score::Mate(-1));
+        assert_eq!(score(250, 3, 10, 2), Score::Centipawns(250));
+    }
+
+    #[test]
+    fn reports_mates() {
+        let back_rank = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        assert_eq!(reported_score(&mut BasicMinimax { depth: 2, quiescence: None, evaluator: Material }, back_rank), Score::Mate(1));
+        assert_eq!(reported_score(&mut Maximax { depth: 2, evaluator: Material }, back_rank), Score::Mate(1));
+        // Black's only move is Kb8, and then Rh8 is mate
+        let lost = "k7/8/1K6/8/8/8/8/7R b - - 0 1";
+        assert_eq!(reported_score(&mut BasicMinimax { depth: 2, quiescence: None, evaluator: Material }, lost), Score::Mate(-1));
+        assert!(matches!(reported_score(&mut BasicMinimax { depth: 2, quiescence: None, evaluator: Material }, "4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), Score::Centipawns(_)));
+    }
+
+    #[test]
+    fn reports_nodes_and_seldepth() {
+        // twenty moves for each side, so 20 positions after one ply and 400 after two
+        let expected = (Some(420), 2);
+        let info = reported_info(&mut BasicMinimax { depth: 1, quiescence: None, evaluator: Material }, Board::STARTING_FEN);
+        assert_eq!((info.nodes, info.seldepth), expected);
+        let info = reported_info(&mut Maximax { depth: 1, evaluator: Material }, Board::STARTING_FEN);
+        assert_eq!((info.nodes, info.seldepth), expected);
+        let info = reported_info(&mut Additive { depth: 1, evaluator: Material }, Board::STARTING_FEN);
+        assert_eq!((info.nodes, info.seldepth), expected);
+        // after 1. e4 d5 there is a capture to look into past the two plies
+        let mut minimax = BasicMinimax { depth: 1, quiescence: Some(Quiescence::new()), evaluator: Material };
+        let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+        let plain = reported_info(&mut BasicMinimax { depth: 1, quiescence: None, evaluator: Material }, fen);
+        let first = reported_info(&mut minimax, fen);
+        assert_eq!(plain.seldepth, 2);
+        assert!(first.seldepth > 2);
+        assert!(first.nodes > plain.nodes);
+        // the counts start over with every search
+        let second = reported_info(&mut minimax, fen);
+        assert_eq!((second.nodes, second.seldepth), (first.nodes, first.seldepth));
+    }
+}
@@ -0,0 +1,154 @@
+use std::cmp::Reverse;
+use crate::board::*;
+
+// Rough piece values for ordering captures, indexed by kind. The king counts as the most valuable
+// attacker, since it can only capture pieces that aren't defended.
+const VALUES: [i32; 6] = [1, 5, 3, 3, 9, 10];
+
+// Ordering scores, so every hint goes before every capture, and so on
+const HINT: i32 = 1 << 30;
+const CAPTURE: i32 = 1 << 29;
+const KILLER: [i32; 2] = [1 << 28, (1 << 28) - 1];
+const MAX_HISTORY: i32 = (1 << 28) - 2;
+
+// Puts the moves most likely to be good first, which is what lets alpha-beta prune: the move from
+// the transposition table or the previous iteration, then captures by most valuable victim and
+// least valuable attacker (MVV-LVA), then the killer moves, which were good in sibling positions,
+// and finally the other quiet moves by how often they have caused cutoffs before (the history
+// heuristic).
+pub struct MoveOrdering {
+    // The last two quiet moves that caused a cutoff at each ply, most recent first
+    killers: Vec<[Option<Move>; 2]>,
+    // Indexed by the square a quiet move is from, then the square it is to
+    history: Vec<[i32; 64]>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> MoveOrdering {
+        MoveOrdering { killers: vec![], history: vec![[0; 64]; 64] }
+    }
+    // Forgets the killers, which belong to the position being searched, and fades out the history
+    // so old cutoffs matter less than new ones
+    pub fn age(&mut self) {
+        self.killers.clear();
+        for scores in &mut self.history {
+            for score in scores {
+                *score /= 2;
+            }
+        }
+    }
+    pub fn is_quiet(mv: &Move) -> bool {
+        mv.attack.is_none() && mv.promote.is_none()
+    }
+    // Captures of big pieces by small ones first, and promotions count as capturing the new piece
+    pub fn mvv_lva(mv: &Move) -> i32 {
+        let victim = mv.attack.map_or(0, |(piece, _)| VALUES[piece.kind as usize]) + mv.promote.map_or(0, |piece| VALUES[piece.kind as usize]);
+        victim * 16 - VALUES[mv.piece.kind as usize]
+    }
+    fn score(&self, mv: &Move, hint: Option<Move>, ply: usize) -> i32 {
+        if hint == Some(*mv) {
+            HINT
+        }
+        else if !MoveOrdering::is_quiet(mv) {
+            CAPTURE + MoveOrdering::mvv_lva(mv)
+        }
+        else if let Some(killer) = self.killers.get(ply).and_then(|killers| killers.iter().position(|killer| *killer == Some(*mv))) {
+            KILLER[killer]
+        }
+        else {
+            self.history[square(mv.from)][square(mv.to)]
+        }
+    }
+    // Sorts the moves of a position ply moves from the root, best first
+    pub fn order(&self, moves: &mut [Move], hint: Option<Move>, ply: usize) {
+        moves.sort_unstable_by_key(|mv| Reverse(self.score(mv, hint, ply)));
+    }
+    // Sorts captures by MVV-LVA alone, for searches that keep no history
+    pub fn order_captures(moves: &mut [Move]) {
+        moves.sort_unstable_by_key(|mv| Reverse(MoveOrdering::mvv_lva(mv)));
+    }
+    // Records a move that caused a beta cutoff with depth plies left to search
+    pub fn cutoff(&mut self, mv: Move, depth: u8, ply: usize) {
+        if !MoveOrdering::is_quiet(&mv) {
+            return;
+        }
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+        // deeper cutoffs save more work, so they count for more
+        let score = &mut self.history[square(mv.from)][square(mv.to)];
+        *score = (*score + depth as i32 * depth as i32).min(MAX_HISTORY);
+    }
+}
+impl Default for MoveOrdering {
+    fn default() -> MoveOrdering {
+        MoveOrdering::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // White can take the queen with a pawn or a pawn with the queen, or make one of many quiet moves
+    const POSITION: &str = "4k3/8/8/3q3p/4P3/8/8/4K2Q w - - 0 1";
+
+    fn ordered(ordering: &MoveOrdering, board: &Board, hint: Option<Move>, ply: usize) -> Vec<Move> {
+        let mut moves = board.all_moves();
+        ordering.order(&mut moves, hint, ply);
+        moves.to_vec()
+    }
+
+    #[test]
+    fn hint_then_captures_then_killers() {
+        let board = Board::from_fen(POSITION).unwrap();
+        let mv = |text: &str| board.parse_uci_move(text).unwrap();
+        let mut ordering = MoveOrdering::new();
+        ordering.cutoff(mv("e1f2"), 1, 3);
+        ordering.cutoff(mv("h1g2"), 1, 3);
+        let moves = ordered(&ordering, &board, Some(mv("e1f1")), 3);
+        assert_eq!(moves[..5], [mv("e1f1"), mv("e4d5"), mv("h1h5"), mv("h1g2"), mv("e1f2")]);
+        // the killers belong to their ply
+        let moves = ordered(&ordering, &board, None, 2);
+        assert_eq!(moves[..2], [mv("e4d5"), mv("h1h5")]);
+        assert!(moves[2..].iter().all(MoveOrdering::is_quiet));
+    }
+
+    #[test]
+    fn mvv_lva_prefers_big_victims_and_small_attackers() {
+        let board = Board::from_fen(POSITION).unwrap();
+        let mv = |text: &str| board.parse_uci_move(text).unwrap();
+        assert!(MoveOrdering::mvv_lva(&mv("e4d5")) > MoveOrdering::mvv_lva(&mv("h1h5")));
+        let promotions = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut moves = promotions.all_moves();
+        MoveOrdering::order_captures(&mut moves);
+        // a promotion counts as capturing the new piece, so taking a rook while promoting to one
+        // beats promoting to a queen
+        let first = ["a7b8q", "a7b8r", "a7a8q"].map(|text| promotions.parse_uci_move(text).unwrap());
+        assert_eq!(moves[..3], first);
+    }
+
+    #[test]
+    fn history_moves_up_quiet_moves_that_cut_off() {
+        let board = Board::from_fen(POSITION).unwrap();
+        let mv = |text: &str| board.parse_uci_move(text).unwrap();
+        let mut ordering = MoveOrdering::new();
+        // cutoffs at another ply only show up in the history, deeper ones counting for more
+        ordering.cutoff(mv("h1f3"), 1, 7);
+        ordering.cutoff(mv("h1h4"), 3, 7);
+        let moves = ordered(&ordering, &board, None, 0);
+        assert_eq!(moves[2..4], [mv("h1h4"), mv("h1f3")]);
+        // captures never go into the history
+        ordering.cutoff(mv("h1h5"), 10, 7);
+        assert_eq!(ordered(&ordering, &board, None, 0)[2..4], [mv("h1h4"), mv("h1f3")]);
+        // ageing halves the history, so a fresh cutoff can overtake an old one
+        ordering.age();
+        ordering.cutoff(mv("h1h2"), 3, 7);
+        assert_eq!(ordered(&ordering, &board, None, 0)[2], mv("h1h2"));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::board::*;
+use crate::eval::{Evaluation, Evaluator, Material, flip, WIN};
+use crate::strategy::ordering::MoveOrdering;
+
+// Keeps searching captures and promotions past the end of the main search until the position is
+// quiet, since a static evaluation in the middle of an exchange can be off by whole pieces. While
+// in check every move is searched instead, as standing still isn't an option.
+pub struct Quiescence {
+    // Skips captures that couldn't bring the score up to alpha even with this much to spare
+    pub delta_margin: Option<Evaluation>,
+    // Positions searched so far, for searches that keep a node budget
+    pub nodes: u64,
+    // The deepest ply reached so far
+    pub seldepth: usize,
+    // Indexed by ply from the root of the main search
+    move_lists: Vec<MoveList>,
+}
+
+impl Quiescence {
+    pub fn new() -> Quiescence {
+        Quiescence {
+            delta_margin: Some(200),
+            nodes: 0,
+            seldepth: 0,
+            move_lists: vec![],
+        }
+    }
+    // The most a move can change the material balance by
+    fn gain(mv: &Move) -> Evaluation {
+        let value = |piece: Piece| Material::evaluate_piece(piece).abs() * 100;
+        let captured = mv.attack.map_or(0, |(piece, _)| value(piece));
+        let promoted = mv.promote.map_or(0, |piece| value(piece) - value(mv.piece));
+        captured + promoted
+    }
+    // Returns the score from the perspective of the side to move, which is ply moves from the root
+    // (for scoring checkmates)
+    pub fn search(&mut self, board: &mut Board, evaluator: &impl Evaluator, ply: usize, mut alpha: Evaluation, beta: Evaluation) -> Evaluation {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        let in_check = board.is_in_check(board.turn);
+        let stand_pat = flip(board.turn) * evaluator.evaluate(board);
+        if !in_check {
+            // the side to move can usually do at least as well as doing nothing special
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+        if self.move_lists.len() <= ply {
+            self.move_lists.resize_with(ply + 1, MoveList::new);
+        }
+        let moves = &mut self.move_lists[ply];
+        moves.clear();
+        board.generate_moves(if in_check { Stage::Evasions } else { Stage::Captures }, moves);
+        MoveOrdering::order_captures(moves);
+        if in_check && moves.is_empty() {
+            return -(WIN - ply as Evaluation);
+        }
+        for ix in 0..self.move_lists[ply].len() {
+            let mv = self.move_lists[ply][ix];
+            if let Some(margin) = self.delta_margin {
+                if !in_check && stand_pat + Quiescence::gain(&mv) + margin < alpha {
+                    continue;
+                }
+            }
+            let undo = board.make_move(mv);
+            let score = -self.search(board, evaluator, ply+1, -beta, -alpha);
+            board.unmake_move(mv, undo);
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        alpha
+    }
+}
+impl Default for Quiescence {
+    fn default() -> Quiescence {
+        Quiescence::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::SearchLimits;
+    use crate::strategy::alphabeta::AlphaBeta;
+
+    #[test]
+    fn sees_past_the_horizon() {
+        // the pawn on d5 is defended, so taking it loses the queen one ply past the search
+        let board = Board::from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let grab = board.parse_uci_move("d1d5").unwrap();
+        let mut strategy = AlphaBeta::new(1);
+        strategy.quiescence = None;
+        assert_eq!(strategy.search(&board, &SearchLimits::default()).unwrap().0, grab);
+        let mut strategy = AlphaBeta::new(1);
+        strategy.quiescence = Some(Quiescence::new());
+        assert_ne!(strategy.search(&board, &SearchLimits::default()).unwrap().0, grab);
+    }
+
+    #[test]
+    fn stands_pat_in_quiet_positions() {
+        let quiet = Board::from_fen("4k3/8/8/3p4/8/8/8/R3K3 w - - 0 1").unwrap();
+        let static_eval = Material.evaluate(&quiet);
+        let mut quiescence = Quiescence::new();
+        assert_eq!(quiescence.search(&mut quiet.clone(), &Material, 0, -WIN, WIN), static_eval);
+        assert_eq!(quiescence.nodes, 1);
+        // with a capture on offer, a static evaluation that already reaches beta is good enough
+        let capture = Board::from_fen("4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let static_eval = Material.evaluate(&capture);
+        let mut quiescence = Quiescence::new();
+        assert_eq!(quiescence.search(&mut capture.clone(), &Material, 0, -WIN, static_eval), static_eval);
+        assert_eq!(quiescence.nodes, 1);
+        // and a capture that can't bring the score up to alpha, even with the margin, isn't tried
+        let mut quiescence = Quiescence::new();
+        let alpha = static_eval + 500;
+        assert_eq!(quiescence.search(&mut capture.clone(), &Material, 0, alpha, WIN), alpha);
+        assert_eq!(quiescence.nodes, 1);
+        let mut quiescence = Quiescence { delta_margin: None, ..Quiescence::new() };
+        quiescence.search(&mut capture.clone(), &Material, 0, alpha, WIN);
+        assert_eq!(quiescence.nodes, 2);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::board::*;
-use crate::strategy::Strategy;
+use crate::strategy::{SearchLimits, Strategy};
 use rand::prelude::*;
 
 pub struct Random(StdRng);
@@ -10,13 +10,7 @@ impl Random {
     }
 }
 impl Strategy for Random {
-    fn choose_move(&mut self, board: &Board) -> Move {
-        let mut all_moves = vec![];
-        for x in 0..8 {
-            for y in 0..8 {
-                all_moves.extend(board.get_moves_for(Loc(x, y)));
-            }
-        }
-        *all_moves.choose(&mut self.0).unwrap()
+    fn choose_move(&mut self, board: &Board, _limits: &SearchLimits) -> Option<Move> {
+        board.all_moves().choose(&mut self.0).copied()
     }
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+use crate::board::*;
+use crate::eval::{Evaluation, WIN};
+
+// How a stored score relates to the true score of the position
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Bound {
+    Exact,
+    // The true score is at least this high (the search failed high)
+    Lower,
+    // The true score is at most this high (the search failed low)
+    Upper,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Entry {
+    pub hash: u64,
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: Evaluation,
+    pub best_move: Option<Move>,
+}
+
+// A fixed-size table of search results indexed by Zobrist hash. When two positions compete for a
+// slot, the newer result wins unless the older one came from a deeper search of the same position.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    // The size is rounded up to a power of two
+    pub fn new(size: usize) -> TranspositionTable {
+        TranspositionTable { entries: vec![None; size.max(1).next_power_of_two()] }
+    }
+    fn slot(&self, hash: u64) -> usize {
+        (hash as usize) & (self.entries.len() - 1)
+    }
+    pub fn probe(&self, hash: u64) -> Option<Entry> {
+        self.entries[self.slot(hash)].filter(|entry| entry.hash == hash)
+    }
+    pub fn store(&mut self, entry: Entry) {
+        let slot = self.slot(entry.hash);
+        if let Some(old) = self.entries[slot] {
+            if old.hash == entry.hash && old.depth > entry.depth {
+                return;
+            }
+        }
+        self.entries[slot] = Some(entry);
+    }
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+// Mate scores count plies from the root, but a table entry can be reached at any ply, so they are
+// stored counting from the position itself instead
+pub fn score_to_table(score: Evaluation, ply: usize) -> Evaluation {
+    if score > WIN / 2 {
+        score + ply as Evaluation
+    }
+    else if score < -WIN / 2 {
+        score - ply as Evaluation
+    }
+    else {
+        score
+    }
+}
+pub fn score_from_table(score: Evaluation, ply: usize) -> Evaluation {
+    if score > WIN / 2 {
+        score - ply as Evaluation
+    }
+    else if score < -WIN / 2 {
+        score + ply as Evaluation
+    }
+    else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(hash: u64, depth: u8, score: Evaluation) -> Entry {
+        Entry { hash, depth, bound: Bound::Exact, score, best_move: None }
+    }
+
+    #[test]
+    fn stores_and_probes() {
+        let mut table = TranspositionTable::new(16);
+        let board = Board::new();
+        let mv = board.parse_uci_move("e2e4").unwrap();
+        let stored = Entry { best_move: Some(mv), bound: Bound::Lower, ..entry(board.hash, 3, 25) };
+        table.store(stored);
+        assert_eq!(table.probe(board.hash), Some(stored));
+        // a position sharing the slot but not the hash finds nothing
+        assert_eq!(table.probe(board.hash ^ 16), None);
+        table.clear();
+        assert_eq!(table.probe(board.hash), None);
+    }
+
+    #[test]
+    fn prefers_deeper_results_for_the_same_position() {
+        let mut table = TranspositionTable::new(16);
+        table.store(entry(5, 4, 100));
+        table.store(entry(5, 2, 200));
+        assert_eq!(table.probe(5).unwrap().score, 100);
+        table.store(entry(5, 4, 300));
+        assert_eq!(table.probe(5).unwrap().score, 300);
+        // a different position always takes over the slot
+        table.store(entry(5 + 16, 1, 400));
+        assert_eq!(table.probe(5), None);
+        assert_eq!(table.probe(5 + 16).unwrap().score, 400);
+    }
+
+    #[test]
+    fn mate_scores_round_trip() {
+        // mate in three plies from the root, found at ply 2, is mate in one ply from there
+        assert_eq!(score_to_table(WIN - 3, 2), WIN - 1);
+        assert_eq!(score_to_table(-(WIN - 3), 2), -(WIN - 1));
+        for score in [WIN - 3, -(WIN - 3), WIN - 10, 150, -150, 0] {
+            for ply in [0, 1, 5] {
+                assert_eq!(score_from_table(score_to_table(score, ply), ply), score);
+            }
+        }
+        // an entry stored at one ply and read at another gives the mate distance from there
+        assert_eq!(score_from_table(score_to_table(WIN - 5, 4), 2), WIN - 3);
+        assert_eq!(score_to_table(150, 4), 150);
+    }
+}
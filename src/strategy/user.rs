@@ -10,7 +10,7 @@ pub fn print_plain(board: &Board) {
     for y in (0..8).rev() {
         print!("{:}  ", y+1);
         for x in 0..8 {
-            print!("{:}", board[Loc(x, y)].map(|piece| to_char(piece)).unwrap_or('.'));
+            print!("{:}", board[Loc(x, y)].map(to_char).unwrap_or('.'));
         }
         println!("  {:}", y+1);
     }
@@ -33,7 +33,7 @@ pub fn print_termion(board: &Board) {
                 Some(Piece { color: Color::White, .. }) => print!("{:}", Fg(White)),
                 Some(Piece { color: Color::Black, .. }) => print!("{:}", Fg(Black)),
             }
-            print!("{:}", board[Loc(x, y)].map(|piece| to_emoji(piece)).unwrap_or(' '));
+            print!("{:}", board[Loc(x, y)].map(to_emoji).unwrap_or(' '));
             print!("{:}{:}", Bg(Reset), Fg(Reset));
         }
         println!("  {:}", y+1);
@@ -76,8 +76,8 @@ fn parse_command(chars: impl Iterator<Item = char>) -> Option<Loc> {
     let mut file: Option<isize> = None;
     let mut rank: Option<isize> = None;
     for ch in chars {
-        if ch >= '1' && ch <= '8' {
-            if rank == None {
+        if ('1'..='8').contains(&ch) {
+            if rank.is_none() {
                 rank = Some((ch as isize) - ('1' as isize))
             }
             else {
@@ -85,8 +85,8 @@ fn parse_command(chars: impl Iterator<Item = char>) -> Option<Loc> {
                 return None
             }
         }
-        else if ch >= 'a' && ch <= 'h' {
-            if file == None {
+        else if ('a'..='h').contains(&ch) {
+            if file.is_none() {
                 file = Some((ch as isize) - ('a' as isize))
             }
             else {
@@ -94,8 +94,8 @@ fn parse_command(chars: impl Iterator<Item = char>) -> Option<Loc> {
                 return None
             }
         }
-        else if ch >= 'A' && ch <= 'H' {
-            if file == None {
+        else if ('A'..='H').contains(&ch) {
+            if file.is_none() {
                 file = Some((ch as isize) - ('A' as isize))
             }
             else {
@@ -112,7 +112,13 @@ fn parse_command(chars: impl Iterator<Item = char>) -> Option<Loc> {
     Some(Loc(file, rank))
 }
 
-fn get_command() -> Loc {
+enum Command {
+    Select(Loc),
+    Play(Move),
+}
+
+// Reads either the square of a piece to move, or a whole move in SAN or UCI notation
+fn get_command(board: &Board) -> Command {
     let mut stdout = io::stdout();
     let stdin = io::stdin();
     loop {
@@ -120,18 +126,28 @@ fn get_command() -> Loc {
         stdout.flush().unwrap();
         let mut line = String::new();
         stdin.lock().read_line(&mut line).unwrap();
+        let text = line.trim();
+        // "e4" could be a square or a pawn move, so it only selects a square if there is a piece on it
+        if let Some(loc) = parse_square(&text.to_ascii_lowercase()) {
+            if board[loc].is_some() {
+                return Command::Select(loc)
+            }
+        }
+        if let Some(mv) = board.parse_san(text).or_else(|| board.parse_uci_move(text)) {
+            return Command::Play(mv)
+        }
         let chs = line.chars().peekable();
         if let Some(loc) = parse_command(chs) {
-            return loc
+            return Command::Select(loc)
         }
     }
 }
 
-fn get_move(moves: Vec<Move>) -> Option<Move> {
+fn get_move(board: &Board, moves: Vec<Move>) -> Option<Move> {
     let mut stdout = io::stdout();
     let stdin = io::stdin();
     for (i, mv) in moves.iter().enumerate() {
-        println!("{:}. {:?}", i+1, mv)
+        println!("{:}. {:}", i+1, mv.to_san(board))
     }
     print!("> ");
     stdout.flush().unwrap();
@@ -147,27 +163,33 @@ fn get_move(moves: Vec<Move>) -> Option<Move> {
 }
 
 impl Strategy for User {
-    fn choose_move(&mut self, board: &Board) -> Move {
+    fn choose_move(&mut self, board: &Board, _limits: &SearchLimits) -> Option<Move> {
+        if board.all_moves().is_empty() {
+            return None;
+        }
         println!();
         println!();
         println!();
         print_termion(board);
         println!();
         loop {
-            let loc = get_command();
-            if board[loc] == None {
+            let loc = match get_command(board) {
+                Command::Select(loc) => loc,
+                Command::Play(mv) => return Some(mv),
+            };
+            if board[loc].is_none() {
                 println!("No piece here");
                 continue;
             }
-            println!("{:?}", loc);
+            println!("{:}", square_name(loc));
             println!("{:?}", board[loc]);
-            let moves = board.get_moves_for(loc);
-            if moves.len() == 0 {
+            let moves = board.legal_moves_for(loc);
+            if moves.is_empty() {
                 println!("You cannot move this piece");
                 continue;
             }
-            if let Some(mv) = get_move(moves) {
-                return mv;
+            if let Some(mv) = get_move(board, moves) {
+                return Some(mv);
             }
         }
     }
@@ -0,0 +1,137 @@
+use std::io::{BufRead, Write};
+use crate::board::*;
+use crate::eval::{Evaluation, Positional, PARAMETERS};
+use crate::pgn::Game;
+
+// Texel-style tuning: the evaluation of each position, squashed by a sigmoid, predicts the result
+// of the game it came from, and the parameters are adjusted to make that prediction as accurate
+// as possible. Positions are stored one per line as a FEN followed by the result from White's
+// point of view in brackets, e.g. "[1.0]" for a White win.
+
+// Writes the positions of a finished game, labeled with its result. Positions where the side to
+// move is in check are left out, since their evaluation says little about the outcome.
+pub fn write_positions(game: &Game, out: &mut impl Write) -> std::io::Result<()> {
+    let label = match game.result {
+        WinState::Winner(Color::White) => "1.0",
+        WinState::Winner(Color::Black) => "0.0",
+        WinState::Draw => "0.5",
+        WinState::Playing => return Ok(()),
+    };
+    let mut board = game.start.clone();
+    for mv in &game.moves {
+        if !board.is_in_check(board.turn) {
+            writeln!(out, "{} [{}]", board.to_fen(), label)?;
+        }
+        board.apply_move(*mv);
+    }
+    Ok(())
+}
+
+pub fn read_positions(input: impl BufRead) -> Result<Vec<(Board, f64)>, String> {
+    let mut positions = vec![];
+    for (number, line) in input.lines().enumerate() {
+        let line = line.map_err(|err| err.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parse = || {
+            let (fen, label) = line.rsplit_once('[')?;
+            let result = label.trim().strip_suffix(']')?.parse().ok()?;
+            Some((Board::from_fen(fen.trim()).ok()?, result))
+        };
+        positions.push(parse().ok_or(format!("Bad position on line {}: {}", number + 1, line))?);
+    }
+    Ok(positions)
+}
+
+struct Sample {
+    coefficients: Vec<(usize, f64)>,
+    result: f64,
+}
+
+// The expected score for White given an evaluation, with scale setting how quickly it approaches a
+// certain win or loss
+fn sigmoid(evaluation: f64, scale: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scale * evaluation / 400.0))
+}
+
+fn evaluate(parameters: &[f64], sample: &Sample) -> f64 {
+    sample.coefficients.iter().map(|(parameter, coefficient)| parameters[*parameter] * coefficient).sum()
+}
+
+fn mean_error(parameters: &[f64], samples: &[Sample], scale: f64) -> f64 {
+    samples.iter().map(|sample| (sample.result - sigmoid(evaluate(parameters, sample), scale)).powi(2)).sum::<f64>() / samples.len() as f64
+}
+
+// Tunes the parameters of an evaluator with the Adam optimizer, reporting the error along the way.
+// The scale of the sigmoid is fitted to the starting parameters first and then kept fixed.
+pub fn tune(start: &Positional, positions: &[(Board, f64)], iterations: usize, mut report: impl FnMut(usize, f64)) -> Positional {
+    let samples: Vec<Sample> = positions.iter().map(|(board, result)| Sample { coefficients: start.coefficients(board), result: *result }).collect();
+    let mut parameters: Vec<f64> = start.parameters().iter().map(|parameter| *parameter as f64).collect();
+    let scale = (1..=30).map(|step| step as f64 / 10.0)
+        .min_by(|a, b| mean_error(&parameters, &samples, *a).total_cmp(&mean_error(&parameters, &samples, *b)))
+        .unwrap();
+    // the step size is in centipawns
+    let (rate, beta1, beta2, epsilon) = (1.0, 0.9, 0.999, 1e-8);
+    let mut momentum = vec![0.0; PARAMETERS];
+    let mut velocity = vec![0.0; PARAMETERS];
+    for iteration in 0..iterations {
+        let mut gradient = vec![0.0; PARAMETERS];
+        for sample in &samples {
+            let prediction = sigmoid(evaluate(&parameters, sample), scale);
+            // derivative of the squared error with respect to the evaluation
+            let slope = -2.0 * (sample.result - prediction) * prediction * (1.0 - prediction) * scale * 10f64.ln() / 400.0;
+            for (parameter, coefficient) in &sample.coefficients {
+                gradient[*parameter] += slope * coefficient / samples.len() as f64;
+            }
+        }
+        let step = iteration as i32 + 1;
+        for parameter in 0..PARAMETERS {
+            momentum[parameter] = beta1 * momentum[parameter] + (1.0 - beta1) * gradient[parameter];
+            velocity[parameter] = beta2 * velocity[parameter] + (1.0 - beta2) * gradient[parameter].powi(2);
+            let corrected = (momentum[parameter] / (1.0 - beta1.powi(step)), velocity[parameter] / (1.0 - beta2.powi(step)));
+            parameters[parameter] -= rate * corrected.0 / (corrected.1.sqrt() + epsilon);
+        }
+        report(iteration, mean_error(&parameters, &samples, scale));
+    }
+    let mut tuned = start.clone();
+    tuned.set_parameters(&parameters.iter().map(|parameter| parameter.round() as Evaluation).collect::<Vec<_>>());
+    tuned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_round_trip() {
+        let mut game = Game::new("A".into(), "B".into(), Board::new());
+        for text in ["f3", "e5", "g4", "Qh4"] {
+            let mut board = game.start.clone();
+            for mv in &game.moves {
+                board.apply_move(*mv);
+            }
+            game.moves.push(board.parse_san(text).unwrap());
+        }
+        game.result = WinState::Winner(Color::Black);
+        let mut out = vec![];
+        write_positions(&game, &mut out).unwrap();
+        let positions = read_positions(&out[..]).unwrap();
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[0], (Board::new(), 0.0));
+        assert!(read_positions("8/8/8/8/8/8/8/8 w - - 0 1".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn tuning_reduces_the_error() {
+        // a pawn up for White, which only won half the time
+        let positions = vec![
+            (Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap(), 0.5),
+            (Board::from_fen("4k3/8/8/8/8/8/3P4/3K4 b - - 0 1").unwrap(), 0.5),
+        ];
+        let start = Positional::default();
+        let mut errors = vec![];
+        tune(&start, &positions, 20, |_, error| errors.push(error));
+        assert!(errors.last().unwrap() < errors.first().unwrap());
+    }
+}
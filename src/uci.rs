@@ -0,0 +1,175 @@
+use std::io::{self, BufRead};
+use std::time::Duration;
+use crate::board::*;
+use crate::strategy::{BackgroundSearch, Reporter, SearchLimits, Strategy};
+
+// Parses the parameters of a "go" command, where times are given in milliseconds
+pub fn parse_go(args: &[&str]) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().and_then(|value| value.parse::<u64>().ok());
+        match *arg {
+            "depth" => limits.depth = value().map(|depth| depth.min(u8::MAX as u64) as u8),
+            "nodes" => limits.nodes = value(),
+            "movetime" => limits.movetime = value().map(Duration::from_millis),
+            "wtime" => limits.wtime = value().map(Duration::from_millis),
+            "btime" => limits.btime = value().map(Duration::from_millis),
+            "winc" => limits.winc = value().map(Duration::from_millis),
+            "binc" => limits.binc = value().map(Duration::from_millis),
+            "movestogo" => limits.movestogo = value().map(|moves| moves.min(u32::MAX as u64) as u32),
+            "infinite" => limits.infinite = true,
+            _ => {}
+        }
+    }
+    limits
+}
+
+// Parses the arguments of "position startpos|fen <fen> [moves <move>...]"
+pub fn parse_position(args: &[&str]) -> Result<Board, String> {
+    let moves_at = args.iter().position(|arg| *arg == "moves").unwrap_or(args.len());
+    let mut board = match args.first() {
+        Some(&"startpos") => Board::new(),
+        Some(&"fen") => Board::from_fen(&args[1..moves_at].join(" ")).map_err(|err| err.to_string())?,
+        _ => return Err("expected startpos or fen".into()),
+    };
+    for text in args.iter().skip(moves_at + 1) {
+        let mv = board.parse_uci_move(text).ok_or_else(|| format!("illegal move {}", text))?;
+        board.apply_move(mv);
+    }
+    Ok(board)
+}
+
+// A search started by "go", and whether it was told to go on until "stop", in which case its move
+// is only printed once it is stopped
+struct Running {
+    search: BackgroundSearch,
+    infinite: bool,
+}
+
+// A null move tells the GUI there was nothing to play
+fn print_best_move(mv: Option<Move>) {
+    println!("bestmove {}", mv.map_or("0000".into(), |mv| mv.to_uci()));
+}
+
+// Stops the search if it is still running, making sure its move is printed, and hands back its
+// strategy
+fn finish(running: Running) -> Box<dyn Strategy> {
+    let (strategy, mv) = running.search.stop();
+    if running.infinite {
+        print_best_move(mv);
+    }
+    strategy
+}
+
+// Speaks the Universal Chess Interface over stdin and stdout. Searches run in the background so
+// "stop" and "isready" are answered while they go on. The strategy is rebuilt for each new game,
+// so nothing it remembers carries over from the last one.
+pub fn run(name: &str, mut make_strategy: impl FnMut() -> Box<dyn Strategy>) {
+    let mut board = Board::new();
+    // taken by the search while it runs
+    let mut strategy = Some(make_strategy());
+    let mut running: Option<Running> = None;
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        // the GUI should only send these once the search is over, but stop it in case it isn't
+        if matches!(words.first(), Some(&("go" | "position" | "ucinewgame" | "quit"))) {
+            if let Some(previous) = running.take() {
+                strategy = Some(finish(previous));
+            }
+        }
+        match words.first() {
+            Some(&"uci") => {
+                println!("id name corroded-knights {}", name);
+                println!("id author tailcalled");
+                println!("uciok");
+            }
+            Some(&"isready") => println!("readyok"),
+            Some(&"ucinewgame") => {
+                board = Board::new();
+                strategy = Some(make_strategy());
+            }
+            Some(&"position") => match parse_position(&words[1..]) {
+                Ok(new_board) => board = new_board,
+                Err(err) => println!("info string {}", err),
+            },
+            Some(&"go") => {
+                let limits = SearchLimits {
+                    reporter: Some(Reporter::new(|info| println!("info {}", info))),
+                    ..parse_go(&words[1..])
+                };
+                let infinite = limits.infinite;
+                let current = strategy.take().unwrap_or_else(&mut make_strategy);
+                let search = BackgroundSearch::start(current, board.clone(), limits, move |mv| {
+                    if !infinite {
+                        print_best_move(mv);
+                    }
+                });
+                running = Some(Running { search, infinite });
+            }
+            Some(&"stop") => {
+                if let Some(previous) = running.take() {
+                    strategy = Some(finish(previous));
+                }
+            }
+            Some(&"quit") => break,
+            // Unknown commands are ignored, as the protocol asks
+            _ => {}
+        }
+    }
+    if let Some(previous) = running {
+        finish(previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<&str> {
+        text.split_whitespace().collect()
+    }
+
+    #[test]
+    fn parses_positions() {
+        assert_eq!(parse_position(&words("startpos")), Ok(Board::new()));
+        let board = parse_position(&words("startpos moves e2e4 c7c5 g1f3")).unwrap();
+        assert_eq!(board.to_fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        let board = parse_position(&words("fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 moves e2e4 e8d7")).unwrap();
+        assert_eq!(board.to_fen(), "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2");
+        let board = parse_position(&words("fen 4k3/P7/8/8/8/8/8/4K3 w - - 0 1 moves a7a8q")).unwrap();
+        assert_eq!(board.to_fen(), "Q3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn rejects_bad_positions() {
+        assert!(parse_position(&words("")).is_err());
+        assert!(parse_position(&words("somewhere")).is_err());
+        assert!(parse_position(&words("fen 8/8/8 w - - 0 1")).is_err());
+        assert_eq!(parse_position(&words("startpos moves e2e5")), Err("illegal move e2e5".into()));
+        assert_eq!(parse_position(&words("startpos moves e2e4 nonsense")), Err("illegal move nonsense".into()));
+    }
+
+    #[test]
+    fn parses_go() {
+        let millis = Duration::from_millis;
+        let limits = parse_go(&words("movetime 1500"));
+        assert_eq!(limits.movetime, Some(millis(1500)));
+        assert_eq!(limits.depth, None);
+        let limits = parse_go(&words("wtime 60000 btime 55000 winc 1000 binc 500 movestogo 12"));
+        assert_eq!((limits.wtime, limits.btime), (Some(millis(60000)), Some(millis(55000))));
+        assert_eq!((limits.winc, limits.binc), (Some(millis(1000)), Some(millis(500))));
+        assert_eq!(limits.movestogo, Some(12));
+        let limits = parse_go(&words("depth 6 nodes 10000 infinite"));
+        assert_eq!((limits.depth, limits.nodes, limits.infinite), (Some(6), Some(10000), true));
+        assert_eq!(parse_go(&words("depth 1000")).depth, Some(u8::MAX));
+    }
+
+    #[test]
+    fn ignores_malformed_go_parameters() {
+        let limits = parse_go(&words("depth deep movetime -5 searchmoves e2e4 wtime"));
+        assert_eq!((limits.depth, limits.movetime, limits.wtime), (None, None, None));
+        assert!(!parse_go(&words("ponder")).infinite);
+    }
+}